@@ -59,11 +59,10 @@ impl ErasedGeneric for Handler {
             .take()
             .expect("fn_table_reads must be filled.");
         let delegator = fn_table
-            .get(&ty_id)
+            .get(ty_id)
             .expect("fn_table_reads doesn't have appropriate entry.");
-        let ret = (delegator)(self);
+        (delegator)(self);
         self.fn_table.generic = Some(fn_table);
-        ret
     }
 
     #[inline]
@@ -74,11 +73,10 @@ impl ErasedGeneric for Handler {
             .take()
             .expect("fn_table_writes must be filled.");
         let delegator = fn_table
-            .get(&ty_id)
+            .get(ty_id)
             .expect("fn_table_writes doesn't have appropriate entry.");
-        let ret = (delegator)(self, input);
+        (delegator)(self, input);
         self.fn_table.generic_writes = Some(fn_table);
-        ret
     }
 
     #[inline]
@@ -89,11 +87,10 @@ impl ErasedGeneric for Handler {
             .take()
             .expect("fn_table_reads must be filled.");
         let delegator = fn_table
-            .get(&ty_id)
+            .get(ty_id)
             .expect("fn_table_reads doesn't have appropriate entry.");
-        let ret = (delegator)(self, output);
+        (delegator)(self, output);
         self.fn_table.generic_reads = Some(fn_table);
-        ret
     }
 
     #[inline]
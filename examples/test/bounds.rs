@@ -0,0 +1,53 @@
+use erased_generic_trait::*;
+
+pub mod a {
+    pub trait Element: 'static {
+        fn id() -> u8 {
+            1
+        }
+    }
+}
+
+pub mod b {
+    pub trait Element: 'static {
+        fn id() -> u8 {
+            2
+        }
+    }
+}
+
+// Bounds sharing the last path segment go into separate groups.
+#[erase_generic(ErasedStore)]
+pub trait Store {
+    fn put_a<E: a::Element>(&mut self, e: &E) -> u8;
+    fn put_b<E: b::Element>(&mut self, e: &E) -> u8;
+}
+
+#[inject_fn_table(
+    Store as ErasedStore;
+    fn put_a<E: a::Element>(&mut self, e: &E) -> u8;
+    fn put_b<E: b::Element>(&mut self, e: &E) -> u8;
+)]
+struct Handler {}
+
+impl Store for Handler {
+    fn put_a<E: a::Element>(&mut self, _e: &E) -> u8 {
+        E::id()
+    }
+
+    fn put_b<E: b::Element>(&mut self, _e: &E) -> u8 {
+        E::id()
+    }
+}
+
+impl a::Element for u8 {}
+impl b::Element for u16 {}
+
+pub fn test() {
+    let handler = Handler {
+        fn_table_erased_store: generate_fn_table!(Handler as ErasedStore; a_element: u8; b_element: u16),
+    };
+    let mut trait_object: Box<dyn ErasedStore> = Box::new(handler);
+    assert_eq!(1, trait_object.put_a(&0_u8));
+    assert_eq!(2, trait_object.put_b(&0_u16));
+}
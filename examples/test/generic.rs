@@ -1,12 +1,17 @@
 use erased_generic_trait::*;
+use std::{pin::Pin, rc::Rc};
 
 // 'static is mandatory.
 pub trait Element: 'static + std::fmt::Debug {}
 
 // `ErasedGeneric` here is the arbitrary trait name to be generated.
 // Please put in any name you want.
+// Async methods in public traits may want `Send` bounds, but it's fine in this test.
+#[allow(async_fn_in_trait)]
 #[erase_generic(ErasedGeneric)]
 pub trait Generic {
+    const VERSION: u32 = 1;
+
     fn generic_no_arg<E: Element>(&mut self);
     fn generic_writes<E: Element>(&mut self, param: &mut E);
     fn generic_reads<E: Element>(&mut self, param: &mut E);
@@ -16,5 +21,42 @@ pub trait Generic {
         param2: &E,
         param3: i32,
     ) -> i32;
+    fn generic_other_symbol<T: Element>(&mut self, param: &T) -> bool;
+    fn generic_where_clause<E>(&mut self, param: &E) -> bool
+    where
+        E: Element;
+    fn generic_impl_trait(&mut self, param: &impl Element) -> bool;
+    fn generic_lifetime<'a, E: Element>(&'a self, key: &'a str, param: &E) -> &'a str;
+    fn generic_elided_lifetime<E: Element>(&self, param: &E) -> &str;
+    fn lifetime<'a>(&'a self, key: &'a str) -> &'a str;
+    fn generic_slice<E: Element>(&mut self, param: &[E]) -> usize;
+    fn generic_vec<E: Element>(&mut self, param: &mut Vec<E>);
+    fn generic_option<E: Element>(&mut self, param: Option<&mut E>) -> bool;
+    fn generic_box<E: Element>(&mut self, param: Box<E>);
+    fn generic_for_each<E: Element>(&mut self, f: &mut dyn FnMut(&E));
+    fn generic_callback<E: Element, F: FnMut(&mut E) -> bool>(&mut self, f: F) -> usize;
+    fn generic_unsized<Q: ?Sized + Element>(&mut self, param: &Q) -> usize;
+    fn chunk<const N: usize>(&mut self, data: &[u8; N]) -> usize;
+    fn repeat<const N: core::primitive::u8>(&self, c: char) -> String;
+    fn generic_value<E: Element>(&mut self, param: E);
+    fn generic_take<E: Element>(&mut self) -> E;
+    fn generic_last<E: Element>(&self) -> Option<&E>;
+    fn generic_pinned<E: Element>(self: Pin<&mut Self>, param: &E) -> usize;
+    fn generic_shared<E: Element>(self: Rc<Self>, param: &E) -> usize;
+    fn generic_finish<E: Element>(self: Box<Self>) -> E;
+    async fn generic_async<E: Element>(&mut self, param: &E) -> usize;
+    fn generic_default<E: Element>(&self, param: &E) -> String {
+        format!("{param:?}")
+    }
+    fn default_len(&self) -> usize {
+        self.foo().len()
+    }
+    #[erased(skip)]
+    fn generic_static<E: Element>(&self) -> usize;
+    #[erased(rename = "dyn_foo")]
     fn foo(&self) -> &'static str;
+
+    // Not callable on trait objects, so these are left out of `ErasedGeneric`.
+    fn new(name: &str) -> Self;
+    fn merge(&mut self, other: Self);
 }
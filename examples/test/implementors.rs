@@ -0,0 +1,213 @@
+use erased_generic_trait::*;
+use std::fmt::Debug;
+
+pub trait Element: 'static + Debug {}
+
+impl Element for u8 {}
+impl Element for u32 {}
+impl Element for u64 {}
+
+#[erase_generic(ErasedPrinter)]
+pub trait Printer {
+    fn print<E: Element>(&self, e: &E) -> String;
+}
+
+// Generic parameters and lifetimes of the struct are given to the function table as well.
+#[inject_fn_table(
+    Printer as ErasedPrinter;
+    fn print<E: Element>(&self, e: &E) -> String;
+)]
+struct Prefixed<'a, T: Debug> {
+    prefix: &'a [T],
+}
+
+impl<T: Debug> Printer for Prefixed<'_, T> {
+    fn print<E: Element>(&self, e: &E) -> String {
+        format!("{:?} {e:?}", self.prefix)
+    }
+}
+
+fn make_prefixed(prefix: &[char]) -> Box<dyn ErasedPrinter + '_> {
+    Box::new(Prefixed {
+        fn_table_erased_printer: generate_fn_table!(Prefixed as ErasedPrinter, u8),
+        prefix,
+    })
+}
+
+#[erase_generic(ErasedMachine)]
+pub trait Machine {
+    fn feed<E: Element>(&mut self, e: &E);
+    fn is_running(&self) -> bool;
+}
+
+// Enums are wrapped in `StateWithFnTable`.
+#[inject_fn_table(
+    Machine as ErasedMachine;
+    fn feed<E: Element>(&mut self, e: &E);
+    fn is_running(&self) -> bool;
+)]
+enum State {
+    Idle,
+    Running(usize),
+}
+
+impl Machine for State {
+    fn feed<E: Element>(&mut self, _e: &E) {
+        *self = match self {
+            State::Idle => State::Running(std::mem::size_of::<E>()),
+            State::Running(n) => State::Running(*n + std::mem::size_of::<E>()),
+        };
+    }
+
+    fn is_running(&self) -> bool {
+        matches!(self, State::Running(..))
+    }
+}
+
+// Tuple structs get the function table as the last field.
+#[inject_fn_table(
+    Machine as ErasedMachine;
+    fn feed<E: Element>(&mut self, e: &E);
+    fn is_running(&self) -> bool;
+)]
+struct Counter(usize);
+
+impl Machine for Counter {
+    fn feed<E: Element>(&mut self, _e: &E) {
+        self.0 += 1;
+    }
+
+    fn is_running(&self) -> bool {
+        self.0 > 0
+    }
+}
+
+// Unit structs become tuple structs having only the function table.
+#[inject_fn_table(
+    Machine as ErasedMachine;
+    fn feed<E: Element>(&mut self, e: &E);
+    fn is_running(&self) -> bool;
+)]
+struct Stopped;
+
+impl Machine for Stopped {
+    fn feed<E: Element>(&mut self, _e: &E) {}
+
+    fn is_running(&self) -> bool {
+        false
+    }
+}
+
+#[erase_generic(ErasedSize)]
+pub trait Size {
+    fn size<E: Element>(&self, e: &E) -> usize;
+}
+
+// The injected field is renamed, and filled with `u8` and `u32` by `Default`.
+#[inject_fn_table(
+    Size as ErasedSize, field(#[doc(hidden)] pub handlers), default(u8, u32);
+    fn size<E: Element>(&self, e: &E) -> usize;
+)]
+#[derive(Default, Clone, Debug)]
+pub struct Exact {
+    pub name: String,
+}
+
+impl Size for Exact {
+    fn size<E: Element>(&self, _e: &E) -> usize {
+        std::mem::size_of::<E>()
+    }
+}
+
+// Another implementor of the same trait in the same module.
+#[inject_fn_table(
+    Size as ErasedSize;
+    fn size<E: Element>(&self, e: &E) -> usize;
+)]
+struct Doubled {}
+
+impl Size for Doubled {
+    fn size<E: Element>(&self, _e: &E) -> usize {
+        std::mem::size_of::<E>() * 2
+    }
+}
+
+// The builder, its methods and the injected field have the visibility of the struct.
+mod handlers {
+    use super::{Element, ErasedSize, Size};
+    use erased_generic_trait::*;
+
+    #[inject_fn_table(
+        Size as ErasedSize;
+        fn size<E: Element>(&self, e: &E) -> usize;
+    )]
+    pub struct Handler<T> {
+        pub value: T,
+    }
+
+    impl<T> Size for Handler<T> {
+        fn size<E: Element>(&self, _e: &E) -> usize {
+            std::mem::size_of::<E>()
+        }
+    }
+}
+
+pub fn test() {
+    // Calls methods of a struct borrowing something.
+    let prefix = vec!['a'];
+    let trait_object = make_prefixed(&prefix);
+    assert_eq!("['a'] 1", trait_object.print(&1_u8));
+
+    // Calls methods of an enum through its wrapper.
+    let state = StateWithFnTable {
+        inner: State::Idle,
+        fn_table_erased_machine: generate_fn_table!(State as ErasedMachine, u32),
+    };
+    assert!(!state.is_running());
+    let mut trait_object: Box<dyn ErasedMachine> = Box::new(state);
+    assert!(!trait_object.is_running());
+    trait_object.feed(&0_u32);
+    assert!(trait_object.is_running());
+
+    // Calls methods of a tuple struct and a unit struct.
+    let mut counter = Counter(0, generate_fn_table!(Counter as ErasedMachine));
+    counter.1.add::<u32>();
+    let stopped = Stopped(generate_fn_table!(Stopped as ErasedMachine, u32));
+    let mut trait_objects: Vec<Box<dyn ErasedMachine>> = vec![Box::new(counter), Box::new(stopped)];
+    for trait_object in &mut trait_objects {
+        trait_object.feed(&0_u32);
+    }
+    let running = trait_objects
+        .iter()
+        .map(|t| t.is_running())
+        .collect::<Vec<_>>();
+    assert_eq!(running, [true, false]);
+
+    // Calls methods through the renamed field filled by `Default`.
+    let mut exact = Exact::default();
+    exact.handlers.add::<u64>();
+    assert!(exact.name.is_empty());
+    assert!(format!("{exact:?}").starts_with("Exact { name: \"\", handlers: "));
+
+    // Calls methods of several implementors of the same trait.
+    let trait_objects: Vec<Box<dyn ErasedSize>> = vec![
+        Box::new(exact.clone()),
+        Box::new(Doubled {
+            fn_table_erased_size: generate_fn_table!(Doubled as ErasedSize, u8, u32, u64),
+        }),
+    ];
+    let sizes = trait_objects
+        .iter()
+        .map(|t| [t.size(&0_u8), t.size(&0_u32), t.size(&0_u64)])
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, [[1, 4, 8], [2, 8, 16]]);
+
+    // Calls methods of a struct in another module.
+    let handler = handlers::Handler {
+        value: 0_u32,
+        fn_table_erased_size: generate_fn_table!(handlers::Handler<u32> as ErasedSize, u64),
+    };
+    assert_eq!(0, handler.value);
+    let trait_object: Box<dyn ErasedSize> = Box::new(handler);
+    assert_eq!(8, trait_object.size(&0_u64));
+}
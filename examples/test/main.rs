@@ -3,14 +3,22 @@ use erased_generic_trait::*;
 use std::{
     any::{Any, TypeId},
     fmt::Debug,
+    future::Future,
+    pin::{pin, Pin},
+    rc::Rc,
+    task::{Context, Poll, Waker},
 };
+mod bounds;
 mod generic;
+mod implementors;
+mod traits;
 use generic::*;
 
-// `ErasedGeneric` here is the trait name you used.
+// `ErasedGeneric` here is the trait name you used, and `Generic` is the generic trait.
 // Other signatures must be exactly same with methods in the trait.
 #[inject_fn_table(
-    ErasedGeneric;
+    Generic as ErasedGeneric;
+    const VERSION: u32;
     fn generic_no_arg<E: Element>(&mut self);
     fn generic_writes<E: Element>(&mut self, param: &mut E);
     fn generic_reads<E: Element>(&mut self, param: &mut E);
@@ -20,14 +28,46 @@ use generic::*;
         param2: &E,
         param3: i32
     ) -> i32;
+    fn generic_other_symbol<T: Element>(&mut self, param: &T) -> bool;
+    fn generic_where_clause<E>(&mut self, param: &E) -> bool
+    where
+        E: Element;
+    fn generic_impl_trait(&mut self, param: &impl Element) -> bool;
+    fn generic_lifetime<'a, E: Element>(&'a self, key: &'a str, param: &E) -> &'a str;
+    fn generic_elided_lifetime<E: Element>(&self, param: &E) -> &str;
+    fn lifetime<'a>(&'a self, key: &'a str) -> &'a str;
+    fn generic_slice<E: Element>(&mut self, param: &[E]) -> usize;
+    fn generic_vec<E: Element>(&mut self, param: &mut Vec<E>);
+    fn generic_option<E: Element>(&mut self, param: Option<&mut E>) -> bool;
+    fn generic_box<E: Element>(&mut self, param: Box<E>);
+    fn generic_for_each<E: Element>(&mut self, f: &mut dyn FnMut(&E));
+    fn generic_callback<E: Element, F: FnMut(&mut E) -> bool>(&mut self, f: F) -> usize;
+    fn generic_unsized<Q: ?Sized + Element>(&mut self, param: &Q) -> usize;
+    fn chunk<const N: usize>(&mut self, data: &[u8; N]) -> usize;
+    fn repeat<const N: core::primitive::u8>(&self, c: char) -> String;
+    fn generic_value<E: Element>(&mut self, param: E);
+    fn generic_take<E: Element>(&mut self) -> E;
+    fn generic_last<E: Element>(&self) -> Option<&E>;
+    fn generic_pinned<E: Element>(self: Pin<&mut Self>, param: &E) -> usize;
+    fn generic_shared<E: Element>(self: Rc<Self>, param: &E) -> usize;
+    fn generic_finish<E: Element>(self: Box<Self>) -> E;
+    async fn generic_async<E: Element>(&mut self, param: &E) -> usize;
+    fn generic_default<E: Element>(&self, param: &E) -> String;
+    fn default_len(&self) -> usize;
+    #[erased(skip)]
+    fn generic_static<E: Element>(&self) -> usize;
+    #[erased(rename = "dyn_foo")]
     fn foo(&self) -> &'static str;
 )]
 struct Handler {
     v: Vec<Box<dyn Any>>, // Test Vec
+    name: String,
 }
 
 // Your generic implementation.
 impl Generic for Handler {
+    const VERSION: u32 = 2;
+
     fn generic_no_arg<E: Element>(&mut self) {
         println!("generic_no_arg() got an object of {:?}", TypeId::of::<E>());
     }
@@ -59,9 +99,132 @@ impl Generic for Handler {
         param3 + 1
     }
 
+    fn generic_other_symbol<T: Element>(&mut self, _param: &T) -> bool {
+        TypeId::of::<T>() == TypeId::of::<A>()
+    }
+
+    fn generic_where_clause<E>(&mut self, _param: &E) -> bool
+    where
+        E: Element,
+    {
+        TypeId::of::<E>() == TypeId::of::<A>()
+    }
+
+    fn generic_impl_trait(&mut self, param: &impl Element) -> bool {
+        format!("{param:?}") == "A(0)"
+    }
+
+    fn generic_lifetime<'a, E: Element>(&'a self, key: &'a str, _param: &E) -> &'a str {
+        if TypeId::of::<E>() == TypeId::of::<A>() {
+            key
+        } else {
+            &self.name
+        }
+    }
+
+    fn generic_elided_lifetime<E: Element>(&self, _param: &E) -> &str {
+        &self.name
+    }
+
+    fn lifetime<'a>(&'a self, key: &'a str) -> &'a str {
+        if key.is_empty() {
+            &self.name
+        } else {
+            key
+        }
+    }
+
+    fn generic_slice<E: Element>(&mut self, param: &[E]) -> usize {
+        param.len()
+    }
+
+    fn generic_vec<E: Element>(&mut self, param: &mut Vec<E>) {
+        param.pop();
+    }
+
+    fn generic_option<E: Element>(&mut self, param: Option<&mut E>) -> bool {
+        param.is_some()
+    }
+
+    fn generic_box<E: Element>(&mut self, param: Box<E>) {
+        self.v.push(param);
+    }
+
+    fn generic_for_each<E: Element>(&mut self, f: &mut dyn FnMut(&E)) {
+        self.v
+            .iter()
+            .filter_map(|v| v.downcast_ref::<E>())
+            .for_each(f);
+    }
+
+    fn generic_callback<E: Element, F: FnMut(&mut E) -> bool>(&mut self, mut f: F) -> usize {
+        self.v
+            .iter_mut()
+            .filter_map(|v| v.downcast_mut::<E>())
+            .filter_map(|v| f(v).then_some(()))
+            .count()
+    }
+
+    fn generic_unsized<Q: ?Sized + Element>(&mut self, param: &Q) -> usize {
+        std::mem::size_of_val(param)
+    }
+
+    fn chunk<const N: usize>(&mut self, data: &[u8; N]) -> usize {
+        data.iter().map(|&b| b as usize).sum::<usize>() + N
+    }
+
+    fn repeat<const N: core::primitive::u8>(&self, c: char) -> String {
+        std::iter::repeat_n(c, N as usize).collect()
+    }
+
+    fn generic_value<E: Element>(&mut self, param: E) {
+        self.v.push(Box::new(param));
+    }
+
+    fn generic_take<E: Element>(&mut self) -> E {
+        let elem = self.v.pop().expect("There's no elements stacked.");
+        *elem.downcast::<E>().unwrap()
+    }
+
+    fn generic_last<E: Element>(&self) -> Option<&E> {
+        self.v.last().and_then(|v| v.downcast_ref::<E>())
+    }
+
+    fn generic_pinned<E: Element>(self: Pin<&mut Self>, _param: &E) -> usize {
+        self.v.len()
+    }
+
+    fn generic_shared<E: Element>(self: Rc<Self>, _param: &E) -> usize {
+        self.v.iter().filter(|v| v.is::<E>()).count()
+    }
+
+    fn generic_finish<E: Element>(mut self: Box<Self>) -> E {
+        self.generic_take()
+    }
+
+    async fn generic_async<E: Element>(&mut self, param: &E) -> usize {
+        format!("{param:?}").len()
+    }
+
+    fn generic_static<E: Element>(&self) -> usize {
+        std::mem::size_of::<E>()
+    }
+
     fn foo(&self) -> &'static str {
         "1234"
     }
+
+    fn new(name: &str) -> Self {
+        Handler {
+            fn_table_erased_generic: generate_fn_table!(Handler as ErasedGeneric; element: A, B; element_unsized: str; const: 2; const_u8: 3),
+            v: Vec::new(),
+            name: name.to_owned(),
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.v.extend(other.v);
+    }
 }
 
 // Test structs
@@ -78,17 +241,31 @@ impl Element for A {}
 impl Element for B {}
 impl Element for C {}
 impl Element for D {}
+impl Element for str {}
+impl Element for [u8] {}
+
+// Trivial executor for futures that are ready without waiting.
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut fut = pin!(fut);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
 
 fn main() {
     // Let's make an instance that implements generic.
     let mut handler = Handler {
-        // fn_table is injected by `inject_fn_table` macro.
+        // fn_table_erased_generic is injected by `inject_fn_table` macro.
         // You can omit A and B here.
-        fn_table: generate_fn_table!(Handler, A, B),
+        fn_table_erased_generic: generate_fn_table!(Handler as ErasedGeneric; element: A, B; element_unsized: str; const: 2; const_u8: 3),
         v: Vec::new(),
+        name: "handler".to_owned(),
     };
     // We can add more entries before becoming a trait object.
-    add_fn_table!(handler, C, D);
+    add_fn_table!(handler as ErasedGeneric; element: C, D; element_unsized: [u8]; const: 4);
 
     // Constructs a trait object.
     // Currently, we can't add more entries using a trait object.
@@ -118,11 +295,113 @@ fn main() {
     let ret = trait_object.generic_multiple_arguments(&mut A(0), &A(0), 1);
     assert_eq!(2, ret);
 
+    // Calls methods having different generic symbols.
+    assert!(trait_object.generic_other_symbol(&A(0)));
+    assert!(!trait_object.generic_other_symbol(&B(0)));
+
+    // Calls methods having where clauses or `impl Trait` arguments.
+    assert!(trait_object.generic_where_clause(&A(0)));
+    assert!(!trait_object.generic_where_clause(&B(0)));
+    assert!(trait_object.generic_impl_trait(&A(0)));
+    assert!(!trait_object.generic_impl_trait(&B(0)));
+
+    // Calls methods having lifetime parameters.
+    let key = String::from("key");
+    assert_eq!("key", trait_object.generic_lifetime(&key, &A(0)));
+    assert_eq!("handler", trait_object.generic_lifetime(&key, &B(0)));
+    assert_eq!("handler", trait_object.generic_elided_lifetime(&C(0.0)));
+    assert_eq!("key", trait_object.lifetime(&key));
+    assert_eq!("handler", trait_object.lifetime(""));
+
+    // Calls methods taking generic types in containers.
+    assert_eq!(2, trait_object.generic_slice(&[A(0), A(1)]));
+    let mut v = vec![B(0), B(1)];
+    trait_object.generic_vec(&mut v);
+    assert_eq!(vec![B(0)], v);
+    assert!(trait_object.generic_option(Some(&mut C(0.0))));
+    assert!(!trait_object.generic_option::<C>(None));
+    trait_object.generic_box(Box::new(D('4')));
+    let mut d_read = D('_');
+    trait_object.generic_reads(&mut d_read);
+    assert_eq!(D('4'), d_read);
+
+    // Calls methods taking callbacks that receive generic types.
+    trait_object.generic_box(Box::new(A(1)));
+    trait_object.generic_box(Box::new(A(2)));
+    let mut sum = 0;
+    trait_object.generic_for_each(&mut |a: &A| sum += a.0);
+    assert_eq!(3, sum);
+    let n = trait_object.generic_callback(|a: &mut A| {
+        a.0 *= 10;
+        a.0 > 10
+    });
+    assert_eq!(1, n);
+    let mut a_read = A(0);
+    trait_object.generic_reads(&mut a_read);
+    assert_eq!(A(20), a_read);
+    trait_object.generic_reads(&mut a_read);
+    assert_eq!(A(10), a_read);
+
+    // Calls methods taking unsized generic types.
+    assert_eq!(5, trait_object.generic_unsized("hello"));
+    assert_eq!(3, trait_object.generic_unsized(&[1_u8, 2, 3][..]));
+
+    // Calls const generic methods, which are registered by their values.
+    assert_eq!(3 + 2, trait_object.chunk(&[1, 2]));
+    assert_eq!(10 + 4, trait_object.chunk(&[1, 2, 3, 4]));
+    assert_eq!("xxx", trait_object.repeat::<3>('x'));
+
+    // Calls methods taking or returning generic types by value.
+    trait_object.generic_value(B(5));
+    assert_eq!(Some(&B(5)), trait_object.generic_last::<B>());
+    assert_eq!(None, trait_object.generic_last::<A>());
+    assert_eq!(B(5), trait_object.generic_take());
+
     println!("Type A's id: {:?}", TypeId::of::<A>());
     println!("Type B's id: {:?}", TypeId::of::<B>());
     println!("Type C's id: {:?}", TypeId::of::<C>());
     println!("Type D's id: {:?}", TypeId::of::<D>());
 
-    // Calls non-generic method.
+    // Calls non-generic method, which is also renamed in the erased trait.
     assert_eq!("1234", trait_object.foo());
+    assert_eq!("1234", trait_object.dyn_foo());
+
+    // Reads associated constants through accessors.
+    assert_eq!(2, trait_object.erased_const_version());
+
+    // Calls methods having default bodies in the generic trait.
+    assert_eq!("B(8)", trait_object.generic_default(&B(8)));
+    assert_eq!(4, trait_object.default_len());
+
+    // Calls async methods, which return boxed futures in the erased trait.
+    assert_eq!(4, block_on(trait_object.generic_async(&A(1))));
+    assert_eq!(6, block_on(trait_object.generic_async(&C(1.5))));
+
+    // Calls methods taking smart pointer receivers.
+    trait_object.generic_value(C(6.0));
+    assert_eq!(C(6.0), trait_object.generic_finish());
+
+    let new_handler = || {
+        let mut handler = Handler::new("handler");
+        // Skipped methods can be called on the concrete type only.
+        assert_eq!(4, handler.generic_static::<A>());
+        let mut other = Handler::new("other");
+        other.generic_value(A(7));
+        handler.merge(other);
+        handler
+    };
+    let mut pinned: Pin<Box<dyn ErasedGeneric>> = Box::pin(new_handler());
+    assert_eq!(1, pinned.as_mut().generic_pinned(&A(0)));
+    let shared: Rc<dyn ErasedGeneric> = Rc::new(new_handler());
+    assert_eq!(1, shared.clone().generic_shared(&A(0)));
+    assert_eq!(0, shared.generic_shared(&B(0)));
+
+    // Calls methods bounded by traits of the same name in different modules.
+    bounds::test();
+
+    // Calls methods of generic traits and supertraits.
+    traits::test();
+
+    // Calls methods of generic structs, tuple structs, unit structs and enums.
+    implementors::test();
 }
@@ -0,0 +1,133 @@
+use erased_generic_trait::*;
+use std::collections::HashMap;
+
+pub trait Element: 'static + std::fmt::Debug {}
+
+impl Element for u8 {}
+impl Element for char {}
+
+// Generic parameters of the trait are carried onto the erased trait.
+#[erase_generic(ErasedStorage)]
+pub trait Storage<K: std::hash::Hash + Eq> {
+    fn put<E: Element>(&mut self, k: K, e: &E);
+    fn get(&self, k: &K) -> Option<&String>;
+}
+
+#[inject_fn_table(
+    Storage<u32> as ErasedStorage<u32>;
+    fn put<E: Element>(&mut self, k: u32, e: &E);
+    fn get(&self, k: &u32) -> Option<&String>;
+)]
+struct Store {
+    map: HashMap<u32, String>,
+}
+
+impl Storage<u32> for Store {
+    fn put<E: Element>(&mut self, k: u32, e: &E) {
+        self.map.insert(k, format!("{e:?}"));
+    }
+
+    fn get(&self, k: &u32) -> Option<&String> {
+        self.map.get(k)
+    }
+}
+
+// `T` is erased as well, so one trait object serves every `Sink<T>`.
+#[erase_generic(ErasedSink, erase(T))]
+pub trait Sink<T: Element> {
+    fn push(&mut self, t: &T) -> usize;
+    #[erased(skip)]
+    fn len(&self) -> usize;
+}
+
+#[inject_fn_table(
+    Sink<T> as ErasedSink;
+    fn push<T: Element>(&mut self, t: &T) -> usize;
+)]
+struct Log {
+    lines: Vec<String>,
+}
+
+impl<T: Element> Sink<T> for Log {
+    fn push(&mut self, t: &T) -> usize {
+        self.lines.push(format!("{t:?}"));
+        self.lines.len()
+    }
+
+    fn len(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+// Erased supertraits are given to the erased trait.
+#[erase_generic(ErasedBase)]
+pub trait Base {
+    fn name(&self) -> String;
+    fn size<E: Element>(&self, e: &E) -> usize;
+}
+
+#[erase_generic(ErasedDerived, supertrait(Base = ErasedBase))]
+pub trait Derived: Base {
+    fn describe<E: Element>(&self, e: &E) -> String;
+}
+
+#[inject_fn_table(
+    Base as ErasedBase;
+    fn name(&self) -> String;
+    fn size<E: Element>(&self, e: &E) -> usize;
+)]
+#[inject_fn_table(
+    Derived as ErasedDerived;
+    fn describe<E: Element>(&self, e: &E) -> String;
+)]
+struct Layered {}
+
+impl Base for Layered {
+    fn name(&self) -> String {
+        "layered".to_owned()
+    }
+
+    fn size<E: Element>(&self, _e: &E) -> usize {
+        std::mem::size_of::<E>()
+    }
+}
+
+impl Derived for Layered {
+    fn describe<E: Element>(&self, e: &E) -> String {
+        format!("{} {e:?}", self.name())
+    }
+}
+
+pub fn test() {
+    // Calls methods of a trait having its own type parameter.
+    let store = Store {
+        fn_table_erased_storage: generate_fn_table!(Store as ErasedStorage, char),
+        map: HashMap::new(),
+    };
+    let mut trait_object: Box<dyn ErasedStorage<u32>> = Box::new(store);
+    trait_object.put(1, &'a');
+    assert_eq!("'a'", trait_object.get(&1).unwrap());
+
+    // Calls methods of every `Sink<T>` through one trait object.
+    let mut log = Log {
+        fn_table_erased_sink: generate_fn_table!(Log as ErasedSink, u8, char),
+        lines: Vec::new(),
+    };
+    assert_eq!(0, Sink::<u8>::len(&log));
+    log.push(&0_u8);
+    let mut trait_object: Box<dyn ErasedSink> = Box::new(log);
+    assert_eq!(2, trait_object.push(&1_u8));
+    assert_eq!(3, trait_object.push(&'a'));
+
+    // Calls methods of the supertrait, then upcasts to it.
+    let layered = Layered {
+        fn_table_erased_base: generate_fn_table!(Layered as ErasedBase, u8),
+        fn_table_erased_derived: generate_fn_table!(Layered as ErasedDerived, u8),
+    };
+    let trait_object: Box<dyn ErasedDerived> = Box::new(layered);
+    assert_eq!("layered 1", trait_object.describe(&1_u8));
+    assert_eq!("layered", trait_object.name());
+    assert_eq!(1, trait_object.size(&1_u8));
+    let trait_object: Box<dyn ErasedBase> = trait_object;
+    assert_eq!("layered", trait_object.name());
+}
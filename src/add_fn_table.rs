@@ -1,24 +1,21 @@
 use crate::common::*;
 use proc_macro::TokenStream;
 use quote::quote;
+use syn::parse_macro_input;
 
 /// Generates code looks like instance.add::<A>().add::<B>().
 pub fn add_fn_table(input: TokenStream) -> TokenStream {
-    // Generates instance ident.
-    let input = input.to_string();
-    let mut split = input.trim().split(',');
-    let instance_name = split.next().expect("Must put in the name of instance.");
-    let instance_ident = gen_ident(instance_name);
+    // Generates instance path.
+    let input = parse_macro_input!(input as FnTableInput);
+    let instance_path = &input.path;
+    let field_ident = gen_table_field_ident(&input.erased_ident);
 
     // Generates add() chain.
-    let adds = split.map(|ty| {
-        let ident = gen_ident(ty.trim());
-        quote! { .add::<#ident>() }
-    });
+    let adds = input.types.gen_chain("add");
 
     quote! {
-        #instance_ident.fn_table
-        #(#adds)*
+        #instance_path.#field_ident
+        #adds
     }
     .into()
 }
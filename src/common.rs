@@ -1,10 +1,198 @@
-use proc_macro2::Span;
+use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
 use syn::{
-    parse_quote, punctuated, token, FnArg, GenericParam, Generics, Ident, Pat, PatIdent, PatType,
-    Path, PathSegment, Receiver, Signature, Token, TraitBound, Type, TypeParam, TypeParamBound,
-    TypePath, TypeReference, TypeTraitObject,
+    parse_quote, punctuated, token,
+    visit_mut::{self, VisitMut},
+    AssocType, Attribute, ConstParam, Error, FnArg, GenericArgument, GenericParam, Generics, Ident,
+    Lifetime, LifetimeParam, LitStr, ParenthesizedGenericArguments, Pat, PatIdent, PatType, Path,
+    PathArguments, PathSegment, PredicateType, Receiver, Result, ReturnType, Signature, Token,
+    TraitBound, TraitBoundModifier, Type, TypeBareFn, TypeImplTrait, TypeParam, TypeParamBound,
+    TypePath, TypeReference, TypeTraitObject, WherePredicate,
 };
 
+/// Input of `generate_fn_table` and `add_fn_table` macros.
+/// Looks like `handler as ErasedGeneric, A, B; message: C, D; const: 16`.
+/// The erased trait tells which function table is the target.
+/// Structs in other modules or crates are given by their paths like `handlers::Handler`.
+pub struct FnTableInput {
+    pub path: Path,
+    pub erased_ident: Ident,
+    pub types: FnTableTypes,
+}
+
+impl Parse for FnTableInput {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let path = input.parse()?;
+        if !input.peek(Token![as]) {
+            return Err(input.error(
+                "expected the erased trait of the function table like `Handler as ErasedGeneric`",
+            ));
+        }
+        input.parse::<Token![as]>()?;
+        let erased_path = input.parse::<Path>()?;
+        let erased_ident = match erased_path.segments.last() {
+            Some(seg) => seg.ident.clone(),
+            None => return Err(Error::new_spanned(erased_path, "expected the erased trait")),
+        };
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        }
+        let types = input.parse()?;
+        Ok(Self {
+            path,
+            erased_ident,
+            types,
+        })
+    }
+}
+
+/// Types to be added to a function table like `A, B; message: C, D; const: 16`.
+/// Types before the first group are added by `with` or `add`,
+/// and types after a group name are added by the group's methods like `with_message`.
+/// Const values can be given as well as types.
+pub struct FnTableTypes {
+    pub types: punctuated::Punctuated<GenericArgument, Token![,]>,
+    pub groups: Vec<(Ident, punctuated::Punctuated<GenericArgument, Token![,]>)>,
+}
+
+impl Parse for FnTableTypes {
+    fn parse(input: ParseStream) -> Result<Self> {
+        fn parse_types(
+            input: ParseStream,
+        ) -> Result<punctuated::Punctuated<GenericArgument, Token![,]>> {
+            let mut types = punctuated::Punctuated::new();
+            while !input.is_empty() && !input.peek(Token![;]) {
+                types.push_value(input.parse()?);
+                if input.is_empty() || input.peek(Token![;]) {
+                    break;
+                }
+                types.push_punct(input.parse()?);
+            }
+            Ok(types)
+        }
+
+        let types = parse_types(input)?;
+        let mut groups = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![;]>()?;
+            if input.is_empty() {
+                break;
+            }
+            // Group names can be keywords like `const`.
+            let group = Ident::parse_any(input)?;
+            input.parse::<Token![:]>()?;
+            groups.push((group, parse_types(input)?));
+        }
+        Ok(Self { types, groups })
+    }
+}
+
+impl FnTableTypes {
+    /// Generates method call chain like `.with::<A>().with_message::<C>()`.
+    pub fn gen_chain(&self, method: &str) -> TokenStream2 {
+        let method_ident = gen_ident(method);
+        let calls = self
+            .types
+            .iter()
+            .map(|ty| quote! { .#method_ident::<#ty>() });
+        let group_calls = self.groups.iter().flat_map(|(group, types)| {
+            let method_ident = clone_ident_with_prefix(group, &format!("{method}_"));
+            types
+                .iter()
+                .map(move |ty| quote! { .#method_ident::<#ty>() })
+        });
+        quote! { #(#calls)* #(#group_calls)* }
+    }
+}
+
+/// Options of a method given by `#[erased(..)]` attributes.
+/// Looks like `#[erased(skip)]` or `#[erased(rename = "dyn_foo")]`.
+#[derive(Default)]
+pub struct ErasedOptions {
+    /// Leaves the method out of the erased trait.
+    pub skip: bool,
+    /// Name of the erased method instead of `erased_foo`.
+    pub rename: Option<Ident>,
+}
+
+impl ErasedOptions {
+    /// Takes `#[erased(..)]` attributes out of the given attributes.
+    #[allow(dead_code)]
+    pub fn take(attrs: &mut Vec<Attribute>) -> Result<Self> {
+        let mut options = Self::default();
+        let mut res = Ok(());
+        attrs.retain(|attr| {
+            if !attr.path().is_ident("erased") {
+                return true;
+            }
+            if res.is_ok() {
+                res = attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("skip") {
+                        options.skip = true;
+                        Ok(())
+                    } else if meta.path.is_ident("rename") {
+                        let name: LitStr = meta.value()?.parse()?;
+                        options.rename = Some(name.parse()?);
+                        Ok(())
+                    } else {
+                        Err(meta.error("unsupported option; expected `skip` or `rename = \"..\"`"))
+                    }
+                });
+            }
+            false
+        });
+        res.map(|_| options)
+    }
+}
+
+/// Generates the path of the pointer type wrapping unsized arguments of the erased trait,
+/// like `ErasedGenericPtr` for `ErasedGeneric`.
+#[allow(dead_code)]
+pub fn gen_ptr_path(erased_path: &Path) -> Path {
+    let mut path = erased_path.clone();
+    if let Some(last) = path.segments.last_mut() {
+        last.ident = clone_ident_with_suffix(&last.ident, "Ptr");
+        last.arguments = PathArguments::None;
+    }
+    path
+}
+
+/// Generates the name of the function table field for the erased trait,
+/// like `fn_table_erased_generic` for `ErasedGeneric`.
+/// So that a struct can have function tables for several erased traits.
+#[allow(dead_code)]
+pub fn gen_table_field_ident(erased_ident: &Ident) -> Ident {
+    gen_ident(&format!(
+        "fn_table_{}",
+        snake_case(&erased_ident.to_string())
+    ))
+}
+
+/// Generates the path of the function table builder of the struct for the erased trait,
+/// like `handlers::HandlerErasedGenericFnTable` for `handlers::Handler`.
+/// Generic arguments of the struct are kept for the builder with turbofish,
+/// so that the path can be used in expressions.
+#[allow(dead_code)]
+pub fn gen_builder_path(st_path: &Path, erased_ident: &Ident) -> Path {
+    let mut path = st_path.clone();
+    if let Some(seg) = path.segments.last_mut() {
+        seg.ident = gen_builder_ident(&seg.ident, erased_ident);
+        if let PathArguments::AngleBracketed(args) = &mut seg.arguments {
+            args.colon2_token.get_or_insert_with(Default::default);
+        }
+    }
+    path
+}
+
+/// Generates the name of the function table builder of the struct for the erased trait,
+/// like `HandlerErasedGenericFnTable` for `Handler` and `ErasedGeneric`.
+#[allow(dead_code)]
+pub fn gen_builder_ident(st_ident: &Ident, erased_ident: &Ident) -> Ident {
+    format_ident!("{st_ident}{erased_ident}FnTable")
+}
+
 /// Modifies `Ident` name with the given `new_name`.
 #[allow(dead_code)]
 pub fn modify_ident(ident: &mut Ident, new_name: &str) {
@@ -40,12 +228,18 @@ pub fn gen_ident(name: &str) -> Ident {
 
 /// Gets ident and mutability of `FnArg`.
 #[allow(dead_code)]
-pub fn parse_arg(arg: &FnArg) -> (&Ident, &Type, Option<&token::Mut>) {
+pub fn parse_arg(arg: &FnArg) -> Result<(&Ident, &Type, Option<&token::Mut>)> {
     match arg {
         FnArg::Typed(PatType { ty, pat, .. }) => {
             let ident = match pat.as_ref() {
                 Pat::Ident(PatIdent { ident, .. }) => ident,
-                _ => unimplemented!(),
+                pat => {
+                    return Err(Error::new_spanned(
+                        pat,
+                        "only plain identifiers are supported as argument patterns; \
+                        bind the argument to a name and destructure it in the method body",
+                    ))
+                }
             };
             let mutability = match ty.as_ref() {
                 // & or &mut
@@ -54,23 +248,57 @@ pub fn parse_arg(arg: &FnArg) -> (&Ident, &Type, Option<&token::Mut>) {
                 _ => &None,
             }
             .as_ref();
-            (ident, ty.as_ref(), mutability)
+            Ok((ident, ty.as_ref(), mutability))
         }
-        FnArg::Receiver(Receiver { ty, mutability, .. }) => {
-            let ident = match ty.as_ref() {
-                // & or &mut
-                Type::Reference(TypeReference { elem, .. }) => match elem.as_ref() {
-                    Type::Path(TypePath { path, .. }) => &path.segments[0].ident,
-                    _ => unimplemented!(),
-                },
-                // Not a reference
-                _ => unimplemented!(),
+        FnArg::Receiver(receiver @ Receiver { ty, mutability, .. }) => {
+            let ident = get_receiver_self(ty).ok_or_else(|| unsupported_receiver(receiver))?;
+            Ok((ident, ty, mutability.as_ref()))
+        }
+    }
+}
+
+/// Gets `Self` from the receiver type, which is one of `&Self`, `&mut Self`, `Box<Self>`,
+/// `Rc<Self>`, `Arc<Self>`, `Pin<&Self>` and `Pin<&mut Self>`.
+#[allow(dead_code)]
+pub fn get_receiver_self(ty: &Type) -> Option<&Ident> {
+    fn get_self(ty: &Type) -> Option<&Ident> {
+        match ty {
+            Type::Path(TypePath { qself: None, path }) if path.is_ident("Self") => path.get_ident(),
+            _ => None,
+        }
+    }
+
+    match ty {
+        // & or &mut
+        Type::Reference(TypeReference { elem, .. }) => get_self(elem),
+        // Smart pointers
+        Type::Path(TypePath { qself: None, path }) => {
+            let seg = path.segments.last()?;
+            let PathArguments::AngleBracketed(args) = &seg.arguments else {
+                return None;
+            };
+            let Some(GenericArgument::Type(inner)) = args.args.first() else {
+                return None;
             };
-            (ident, ty, mutability.as_ref())
+            match inner {
+                Type::Reference(TypeReference { elem, .. }) if seg.ident == "Pin" => get_self(elem),
+                inner if ["Box", "Rc", "Arc"].iter().any(|p| seg.ident == p) => get_self(inner),
+                _ => None,
+            }
         }
+        _ => None,
     }
 }
 
+/// Makes an error for unsupported receivers.
+fn unsupported_receiver(receiver: &Receiver) -> Error {
+    Error::new_spanned(
+        receiver,
+        "only `&self`, `&mut self`, `Box<Self>`, `Rc<Self>`, `Arc<Self>` and `Pin<&mut Self>` \
+        receivers are supported for now; move this method into a separate trait that isn't erased",
+    )
+}
+
 /// Determines that the given `Type` is a & or &mut.
 #[allow(dead_code)]
 pub fn is_ref(ty: &Type) -> bool {
@@ -78,72 +306,487 @@ pub fn is_ref(ty: &Type) -> bool {
 }
 
 /// Gets generic symbols like *T* from the `Generics`.
+/// Lifetime parameters are skipped because they don't need to be erased.
 #[allow(dead_code)]
-pub fn get_generic_symbols(generics: &Generics) -> Vec<String> {
-    generics.params.iter().map(get_generic_symbol).collect()
+pub fn get_generic_symbols(generics: &Generics) -> Result<Vec<String>> {
+    generics
+        .params
+        .iter()
+        .filter(|param| !matches!(param, GenericParam::Lifetime(..)))
+        .map(get_generic_symbol)
+        .collect()
 }
 
 /// Gets generic symbol like *T* from the `GenericParam`.
 #[allow(dead_code)]
-pub fn get_generic_symbol(param: &GenericParam) -> String {
+pub fn get_generic_symbol(param: &GenericParam) -> Result<String> {
     match param {
-        GenericParam::Type(TypeParam { ident, .. }) => ident.to_string(),
-        _ => unimplemented!(),
+        GenericParam::Type(TypeParam { ident, .. }) => Ok(ident.to_string()),
+        GenericParam::Lifetime(..) => Err(Error::new_spanned(
+            param,
+            "expected a generic type parameter, found a lifetime parameter",
+        )),
+        GenericParam::Const(ConstParam { ident, .. }) => Ok(ident.to_string()),
     }
 }
 
 /// Changes generic reference parameters to `dyn Any` refererences.
+/// Containers of generic types like `&Vec<T>`, `Option<&T>` and `Box<T>`, and callbacks like
+/// `&mut dyn FnMut(&T)` are also changed. See [`ErasedArg`] for the details.
 #[allow(dead_code)]
-pub fn change_arg_to_any<'a>(arg: &mut FnArg, mut targets: impl Iterator<Item = &'a str>) {
+pub fn change_arg_to_any(arg: &mut FnArg, generics: &Generics) -> Result<()> {
     // Gets into `PatType`
     let ty_dest = match arg {
         FnArg::Typed(PatType { ty, .. }) => ty,
         // Doesn't handle `Receiver`.
-        FnArg::Receiver(..) => return,
+        FnArg::Receiver(..) => return Ok(()),
     };
 
-    // Gets into `TypeReference`
-    let (mutability, ty) = match ty_dest.as_mut() {
-        // &T or &mut T
-        Type::Reference(TypeReference {
-            mutability, elem, ..
-        }) => (mutability, elem),
-        // Doesn't support other types for now.
-        _ => return,
-    };
+    // Changes the `ty_dest` with something like `&mut dyn Any` or `&dyn Any`.
+    if let Some(erased) = ErasedArg::new(ty_dest, generics)? {
+        **ty_dest = erased.erased_type(generics)?;
+    }
+    Ok(())
+}
 
-    // Skips not a generic reference.
-    let first_seg = match ty.as_mut() {
-        Type::Path(TypePath {
-            path: Path { segments, .. },
-            ..
-        }) => &mut segments[0],
-        _ => unimplemented!(),
-    };
-    let ty = first_seg.ident.to_string();
-    if targets.all(|t| ty.as_str() != t) {
-        return;
+/// Generic argument types that can be erased.
+#[allow(dead_code)]
+pub enum ErasedArg<'a> {
+    /// `&T` or `&mut T` including containers like `&mut Vec<T>`,
+    /// which becomes `&dyn Any` or `&mut dyn Any`.
+    Ref(&'a TypeReference),
+    /// `&[T]`, `&mut [T]`, or `&T` with `T: ?Sized`, which is unsized,
+    /// so that it's passed as a raw pointer in `&dyn Any`.
+    Unsized(&'a TypeReference),
+    /// `Option<&T>` or `Option<&mut T>`, which becomes `Option<&dyn Any>` or
+    /// `Option<&mut dyn Any>`.
+    Option(&'a TypeReference),
+    /// `Box<T>`, which becomes `Box<dyn Any>`.
+    Box(&'a Type),
+    /// `T` by value, which is boxed into `Box<dyn Any>`.
+    Value(&'a Type),
+    /// Callbacks like `&mut dyn FnMut(&T)` or `F` with `F: FnMut(&T)`,
+    /// which become `&mut dyn FnMut(&dyn Any)`, or `&dyn Fn(&dyn Any)` for `Fn`.
+    Callback(Callback<'a>),
+}
+
+/// Callback argument of a generic method.
+#[allow(dead_code)]
+pub struct Callback<'a> {
+    /// One of `Fn`, `FnMut` and `FnOnce`.
+    pub kind: &'a Ident,
+    /// Arguments and output of the callback.
+    pub args: &'a ParenthesizedGenericArguments,
+    /// Reference of the trait object. `None` if the callback is a generic parameter.
+    pub reference: Option<&'a TypeReference>,
+}
+
+#[allow(dead_code)]
+impl<'a> ErasedArg<'a> {
+    /// Determines the kind of the given argument type.
+    /// Returns `None` if it doesn't need to be erased, or an error if it's not supported.
+    pub fn new(ty: &'a Type, generics: &'a Generics) -> Result<Option<Self>> {
+        // Skips types that don't contain generic symbols at all.
+        let symbols = get_generic_symbols(generics)?;
+        if !mentions_any(ty, symbols.iter().map(|s| s.as_str())) {
+            return Ok(None);
+        }
+
+        let erased = match ty {
+            Type::Reference(reference) => match reference.elem.as_ref() {
+                Type::TraitObject(TypeTraitObject { bounds, .. }) => {
+                    get_callback(bounds).map(|(kind, args)| {
+                        Self::Callback(Callback {
+                            kind,
+                            args,
+                            reference: Some(reference),
+                        })
+                    })
+                }
+                ty if is_sized(ty, generics) => Some(Self::Ref(reference)),
+                // Slices, `str` or `?Sized` generic types.
+                _ => Some(Self::Unsized(reference)),
+            },
+            Type::Path(TypePath { qself: None, path }) => {
+                if let Some((kind, args)) = path
+                    .get_ident()
+                    .and_then(|ident| get_callback_param(generics, ident))
+                {
+                    Some(Self::Callback(Callback {
+                        kind,
+                        args,
+                        reference: None,
+                    }))
+                } else if path.get_ident().is_some() {
+                    is_sized(ty, generics).then_some(Self::Value(ty))
+                } else {
+                    Self::new_container(path, generics)
+                }
+            }
+            _ => None,
+        };
+        erased.map(Some).ok_or_else(|| unsupported_generic_arg(ty))
+    }
+
+    /// Determines the kind of the given container type like `Option<&T>` or `Box<T>`.
+    fn new_container(path: &'a Path, generics: &Generics) -> Option<Self> {
+        let seg = path.segments.last()?;
+        let PathArguments::AngleBracketed(args) = &seg.arguments else {
+            return None;
+        };
+        let Some(GenericArgument::Type(inner)) = args.args.first() else {
+            return None;
+        };
+        if args.args.len() != 1 {
+            return None;
+        }
+        match inner {
+            Type::Reference(reference)
+                if seg.ident == "Option" && is_sized(&reference.elem, generics) =>
+            {
+                Some(Self::Option(reference))
+            }
+            inner if seg.ident == "Box" && is_sized(inner, generics) => Some(Self::Box(inner)),
+            _ => None,
+        }
     }
 
-    // Changes the `ty_dest` with `&mut dyn Any` or `&dyn Any`.
-    let new_type: TypeReference = match mutability {
-        // &mut T
-        Some(_) => parse_quote! { &mut dyn std::any::Any },
-        // &
-        None => parse_quote! { &dyn std::any::Any },
+    /// Gets the erased type like `&dyn Any`.
+    pub fn erased_type(&self, generics: &Generics) -> Result<Type> {
+        let ty = match self {
+            Self::Ref(reference) => {
+                let mut reference = (*reference).clone();
+                *reference.elem = parse_quote! { dyn std::any::Any };
+                Type::Reference(reference)
+            }
+            Self::Unsized(TypeReference { mutability, .. }) => {
+                parse_quote! { &#mutability dyn std::any::Any }
+            }
+            Self::Option(reference) => {
+                let mut reference = (*reference).clone();
+                *reference.elem = parse_quote! { dyn std::any::Any };
+                parse_quote! { std::option::Option<#reference> }
+            }
+            Self::Box(..) | Self::Value(..) => parse_quote! { std::boxed::Box<dyn std::any::Any> },
+            Self::Callback(callback) => {
+                let lifetime = callback.reference.and_then(|r| r.lifetime.as_ref());
+                let inputs = callback
+                    .args
+                    .inputs
+                    .iter()
+                    .map(|ty| match ErasedArg::new(ty, generics)? {
+                        Some(erased) => erased.erased_type(generics),
+                        None => Ok(ty.clone()),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let output = &callback.args.output;
+                if callback.is_fn() {
+                    parse_quote! { &#lifetime dyn Fn(#(#inputs),*) #output }
+                } else {
+                    parse_quote! { &#lifetime mut dyn FnMut(#(#inputs),*) #output }
+                }
+            }
+        };
+        Ok(ty)
+    }
+
+    /// Generates a statement that prepares the argument before it's turned into the erased type.
+    pub fn gen_preproc(&self, ident: &Ident) -> TokenStream2 {
+        match self {
+            Self::Callback(
+                callback @ Callback {
+                    reference: None, ..
+                },
+            ) => {
+                if callback.kind == "FnOnce" {
+                    quote! { let mut #ident = std::option::Option::Some(#ident); }
+                } else if callback.kind == "FnMut" {
+                    quote! { let mut #ident = #ident; }
+                } else {
+                    quote! {}
+                }
+            }
+            _ => quote! {},
+        }
+    }
+
+    /// Generates an expression that turns the argument into the erased type.
+    /// Unsized arguments are wrapped in the pointer type of the erased trait given by `ptr_path`.
+    pub fn gen_upcast(
+        &self,
+        ident: &Ident,
+        generics: &Generics,
+        ptr_path: &Path,
+    ) -> Result<TokenStream2> {
+        let expr = match self {
+            Self::Ref(TypeReference { mutability, .. }) => {
+                quote! { #ident as &#mutability dyn std::any::Any }
+            }
+            // The pointer is made from a reference that lives while the method is called.
+            Self::Unsized(TypeReference {
+                mutability: Some(_),
+                ..
+            }) => quote! {
+                &mut unsafe { #ptr_path::new(#ident as *mut _) } as &mut dyn std::any::Any
+            },
+            Self::Unsized(TypeReference {
+                mutability: None, ..
+            }) => quote! {
+                &unsafe { #ptr_path::new(#ident as *const _) } as &dyn std::any::Any
+            },
+            Self::Option(TypeReference { mutability, .. }) => {
+                quote! { #ident.map(|v| v as &#mutability dyn std::any::Any) }
+            }
+            Self::Box(..) => quote! { #ident as std::boxed::Box<dyn std::any::Any> },
+            Self::Value(..) => {
+                quote! { std::boxed::Box::new(#ident) as std::boxed::Box<dyn std::any::Any> }
+            }
+            // Wraps the callback with a closure that receives erased arguments.
+            Self::Callback(callback) => {
+                let mut params = Vec::new();
+                let mut args = Vec::new();
+                for (i, ty) in callback.args.inputs.iter().enumerate() {
+                    let param = gen_ident(&format!("__arg{i}__"));
+                    match ErasedArg::new(ty, generics)? {
+                        Some(erased) => {
+                            let erased_ty = erased.erased_type(generics)?;
+                            params.push(quote! { #param: #erased_ty });
+                            args.push(erased.gen_downcast(&param, generics, ptr_path)?);
+                        }
+                        None => {
+                            params.push(quote! { #param: #ty });
+                            args.push(quote! { #param });
+                        }
+                    }
+                }
+                let f = if callback.reference.is_none() && callback.kind == "FnOnce" {
+                    quote! { (#ident.take().expect("FnOnce callback must be called once.")) }
+                } else {
+                    quote! { #ident }
+                };
+                if callback.is_fn() {
+                    quote! { &|#(#params),*| #f(#(#args),*) }
+                } else {
+                    quote! { &mut |#(#params),*| #f(#(#args),*) }
+                }
+            }
+        };
+        Ok(expr)
+    }
+
+    /// Generates an expression that turns the erased argument back into the original type.
+    /// Only pointers wrapped by the generated code are accepted for unsized arguments,
+    /// so that raw pointers made by callers are never dereferenced.
+    pub fn gen_downcast(
+        &self,
+        ident: &Ident,
+        generics: &Generics,
+        ptr_path: &Path,
+    ) -> Result<TokenStream2> {
+        let expr = match self {
+            Self::Ref(TypeReference {
+                mutability: Some(_),
+                elem,
+                ..
+            }) => quote! { #ident.downcast_mut::<#elem>().unwrap() },
+            Self::Ref(TypeReference {
+                mutability: None,
+                elem,
+                ..
+            }) => quote! { #ident.downcast_ref::<#elem>().unwrap() },
+            // The pointer can only be made by the generated code from a reference
+            // that lives while the method is called.
+            Self::Unsized(TypeReference {
+                mutability: Some(_),
+                elem,
+                ..
+            }) => quote! {
+                unsafe { &mut *#ident.downcast_ref::<#ptr_path<*mut #elem>>().unwrap().get() }
+            },
+            Self::Unsized(TypeReference {
+                mutability: None,
+                elem,
+                ..
+            }) => quote! {
+                unsafe { &*#ident.downcast_ref::<#ptr_path<*const #elem>>().unwrap().get() }
+            },
+            Self::Option(TypeReference {
+                mutability: Some(_),
+                elem,
+                ..
+            }) => quote! { #ident.map(|v| v.downcast_mut::<#elem>().unwrap()) },
+            Self::Option(TypeReference {
+                mutability: None,
+                elem,
+                ..
+            }) => quote! { #ident.map(|v| v.downcast_ref::<#elem>().unwrap()) },
+            Self::Box(inner) => quote! { #ident.downcast::<#inner>().unwrap() },
+            Self::Value(ty) => quote! { *#ident.downcast::<#ty>().unwrap() },
+            // Wraps the erased callback with a closure that receives original arguments.
+            Self::Callback(callback) => {
+                let mut params = Vec::new();
+                let mut args = Vec::new();
+                for (i, ty) in callback.args.inputs.iter().enumerate() {
+                    let param = gen_ident(&format!("__arg{i}__"));
+                    params.push(quote! { #param: #ty });
+                    args.push(match ErasedArg::new(ty, generics)? {
+                        Some(erased) => erased.gen_upcast(&param, generics, ptr_path)?,
+                        None => quote! { #param },
+                    });
+                }
+                let closure = quote! { move |#(#params),*| #ident(#(#args),*) };
+                match callback.reference {
+                    Some(TypeReference { mutability, .. }) => quote! { &#mutability #closure },
+                    None => closure,
+                }
+            }
+        };
+        Ok(expr)
+    }
+}
+
+impl Callback<'_> {
+    /// Determines that the callback is `Fn`, which is erased into `&dyn Fn`.
+    /// Others are erased into `&mut dyn FnMut`.
+    pub fn is_fn(&self) -> bool {
+        self.kind == "Fn"
+    }
+}
+
+/// Gets `Fn`, `FnMut` or `FnOnce` bound with its arguments from the given bounds.
+#[allow(dead_code)]
+pub fn get_callback(
+    bounds: &punctuated::Punctuated<TypeParamBound, Token![+]>,
+) -> Option<(&Ident, &ParenthesizedGenericArguments)> {
+    bounds.iter().find_map(|bound| match bound {
+        TypeParamBound::Trait(TraitBound { path, .. }) => {
+            let seg = path.segments.last()?;
+            match &seg.arguments {
+                PathArguments::Parenthesized(args)
+                    if ["Fn", "FnMut", "FnOnce"].iter().any(|f| seg.ident == f) =>
+                {
+                    Some((&seg.ident, args))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    })
+}
+
+/// Gets callback bound of the generic parameter named `ident` if it's a callback
+/// like `F: FnMut(&T)`.
+#[allow(dead_code)]
+pub fn get_callback_param<'a>(
+    generics: &'a Generics,
+    ident: &Ident,
+) -> Option<(&'a Ident, &'a ParenthesizedGenericArguments)> {
+    generics
+        .type_params()
+        .find(|param| &param.ident == ident)
+        .and_then(|param| get_callback(&param.bounds))
+}
+
+/// Determines that the given generic parameter is a callback like `F: FnMut(&T)`.
+#[allow(dead_code)]
+pub fn is_callback_param(param: &GenericParam) -> bool {
+    matches!(param, GenericParam::Type(TypeParam { bounds, .. }) if get_callback(bounds).is_some())
+}
+
+/// Determines that the given `Type` is known to be sized.
+/// Slices, `str`, trait objects and generic types bounded by `?Sized` are not sized.
+#[allow(dead_code)]
+pub fn is_sized(ty: &Type, generics: &Generics) -> bool {
+    match ty {
+        Type::Slice(..) | Type::TraitObject(..) => false,
+        Type::Path(TypePath { qself: None, path }) => {
+            !path.is_ident("str")
+                && !generics
+                    .type_params()
+                    .any(|param| path.is_ident(&param.ident) && is_maybe_sized(param))
+        }
+        _ => true,
+    }
+}
+
+/// Determines that the given generic parameter is bounded by `?Sized`.
+#[allow(dead_code)]
+pub fn is_maybe_sized(param: &TypeParam) -> bool {
+    param.bounds.iter().any(|bound| {
+        matches!(
+            bound,
+            TypeParamBound::Trait(TraitBound {
+                modifier: TraitBoundModifier::Maybe(..),
+                ..
+            })
+        )
+    })
+}
+
+#[allow(dead_code)]
+fn unsupported_generic_arg(ty: &Type) -> Error {
+    Error::new_spanned(
+        ty,
+        "generic arguments must be passed as `T`, `&T`, `&mut T`, `&[T]`, `Option<&T>`, `Box<T>` \
+        or callbacks like `&mut dyn FnMut(&T)` for now; change the parameter into one of them",
+    )
+}
+
+/// Gets the erasable kind of the generic return type.
+/// Returns `None` if the return type is not generic.
+#[allow(dead_code)]
+pub fn get_erased_output(sig: &Signature) -> Result<Option<ErasedArg<'_>>> {
+    let ReturnType::Type(_, ty) = &sig.output else {
+        return Ok(None);
     };
-    *ty_dest = Box::new(Type::Reference(new_type));
+    match ErasedArg::new(ty, &sig.generics) {
+        Ok(Some(ErasedArg::Unsized(..) | ErasedArg::Callback(..))) | Err(..) => {
+            Err(Error::new_spanned(
+                ty,
+                "generic return types must be `T`, `&T`, `&mut T`, `Option<&T>` or `Box<T>` \
+                for now; change the return type into one of them",
+            ))
+        }
+        erased => erased,
+    }
+}
+
+/// Changes the generic return type to `Box<dyn Any>` or `dyn Any` references.
+#[allow(dead_code)]
+pub fn change_output_to_any(sig: &mut Signature) -> Result<()> {
+    if let Some(erased) = get_erased_output(sig)? {
+        let ty = erased.erased_type(&sig.generics)?;
+        sig.output = parse_quote! { -> #ty };
+    }
+    Ok(())
 }
 
 /// Changes generic reference parameters to `dyn Any` refererences.
 #[allow(dead_code)]
 pub fn change_args_to_anys<'a>(
     args: impl Iterator<Item = &'a mut FnArg>,
-    targets: impl Iterator<Item = &'a str> + Clone,
-) {
+    generics: &Generics,
+) -> Result<()> {
     for arg in args {
-        change_arg_to_any(arg, targets.clone());
+        change_arg_to_any(arg, generics)?;
     }
+    Ok(())
+}
+
+/// Determines that the given tokens contain any of `targets` as an identifier.
+#[allow(dead_code)]
+pub fn mentions_any<'a>(
+    tokens: &impl ToTokens,
+    targets: impl Iterator<Item = &'a str> + Clone,
+) -> bool {
+    fn visit<'a>(tokens: TokenStream2, targets: &(impl Iterator<Item = &'a str> + Clone)) -> bool {
+        tokens.into_iter().any(|tt| match tt {
+            TokenTree::Ident(ident) => targets.clone().any(|t| ident == t),
+            TokenTree::Group(group) => visit(group.stream(), targets),
+            _ => false,
+        })
+    }
+    visit(tokens.to_token_stream(), &targets)
 }
 
 /// Gets `Ident` if `FnArg` has the name of `target` as its type's `Ident`.
@@ -152,7 +795,7 @@ pub fn get_matched_ident<'a>(arg: &'a FnArg, target: &str) -> Option<&'a Ident>
     let first_seg = get_nth_pathseg(arg, 0)?;
     let ty = first_seg.ident.to_string();
     if ty.as_str() == target {
-        let (ident, _, _) = parse_arg(arg);
+        let (ident, _, _) = parse_arg(arg).ok()?;
         Some(ident)
     } else {
         None
@@ -209,36 +852,345 @@ pub fn get_nth_pathseg(arg: &FnArg, n: usize) -> Option<&PathSegment> {
 
 /// Makes a generic method become non-generic.
 #[allow(dead_code)]
-pub fn modify_signature_to_erased(sig: &mut Signature) {
+pub fn modify_signature_to_erased(sig: &mut Signature) -> Result<()> {
+    validate_signature(sig)?;
+
     // Modifies fn names.
     let new_name = format!("erased_{}", sig.ident.to_string().as_str());
     modify_ident(&mut sig.ident, new_name.as_str());
 
     // Skips if non-generic.
     if !is_generic(sig) {
-        return;
+        into_boxed_future(sig);
+        return Ok(());
     }
 
-    // Gets generic symbols.
-    let symbols = get_generic_symbols(&sig.generics);
+    // Makes `impl Trait` arguments and where clauses into generic parameters.
+    *sig = normalize_generics(sig);
+
+    // Gets generic symbols and the key parameter to find out the function to be called.
+    let generics = sig.generics.clone();
+    let symbols = get_generic_symbols(&generics)?;
+    let key = gen_key_arg(&generics);
 
-    // Remove the `Generics` now.
+    // Remove the type parameters now, but keeps lifetime parameters and predicates that don't
+    // need the generic symbols.
+    let where_clause = sig
+        .generics
+        .where_clause
+        .take()
+        .and_then(|mut where_clause| {
+            where_clause.predicates = where_clause
+                .predicates
+                .into_iter()
+                .filter(|pred| !mentions_any(pred, symbols.iter().map(|s| s.as_str())))
+                .collect();
+            (!where_clause.predicates.is_empty()).then_some(where_clause)
+        });
+    let lifetimes = sig
+        .generics
+        .lifetimes()
+        .cloned()
+        .map(GenericParam::Lifetime)
+        .collect::<punctuated::Punctuated<_, Token![,]>>();
     remove_generics(&mut sig.generics);
+    if !lifetimes.is_empty() {
+        sig.generics.lt_token = Some(Default::default());
+        sig.generics.params = lifetimes;
+        sig.generics.gt_token = Some(Default::default());
+    }
+    sig.generics.where_clause = where_clause;
 
     // Change generic symbols in parameters into `dyn Any`.
     for arg in sig.inputs.iter_mut() {
-        change_arg_to_any(arg, symbols.iter().map(|s| s.as_str()));
+        change_arg_to_any(arg, &generics)?;
     }
+    let mut output_sig = sig.clone();
+    output_sig.generics = generics;
+    change_output_to_any(&mut output_sig)?;
+    sig.output = output_sig.output;
+    into_boxed_future(sig);
 
-    // Injects `__type_id__: &TypeId` as the second parameter.
-    inject_type_id(sig);
+    // Injects `__type_id__: &TypeId` or `__const_key__` as the second parameter.
+    inject_key(sig, key);
+    Ok(())
 }
 
-/// Injects `__type_id__: &TypeId` as the second parameter.
+/// Prefix of generic parameters made from `impl Trait` arguments.
+const IMPL_TRAIT_PREFIX: &str = "__ImplTrait";
+
+/// Makes generic parameters explicit.
+/// - `impl Trait` arguments become generic parameters like `__ImplTrait0: Trait`.
+/// - Where clauses bounding generic parameters are merged into the parameters.
 #[allow(dead_code)]
-pub fn inject_type_id(sig: &mut Signature) {
-    let param: FnArg = parse_quote! { __type_id__: &std::any::TypeId };
-    sig.inputs.insert(1, param);
+pub fn normalize_generics(sig: &Signature) -> Signature {
+    struct Desugar(Vec<TypeParam>);
+
+    impl VisitMut for Desugar {
+        fn visit_type_mut(&mut self, ty: &mut Type) {
+            if let Type::ImplTrait(TypeImplTrait { bounds, .. }) = ty {
+                let ident = gen_ident(&format!("{IMPL_TRAIT_PREFIX}{}", self.0.len()));
+                self.0.push(parse_quote! { #ident: #bounds });
+                *ty = parse_quote! { #ident };
+            } else {
+                visit_mut::visit_type_mut(self, ty);
+            }
+        }
+
+        // `impl Trait` is not allowed in function types.
+        fn visit_type_bare_fn_mut(&mut self, _: &mut TypeBareFn) {}
+        fn visit_parenthesized_generic_arguments_mut(
+            &mut self,
+            _: &mut ParenthesizedGenericArguments,
+        ) {
+        }
+    }
+
+    let mut sig = sig.clone();
+
+    // `impl Trait` to generic parameters.
+    let mut desugar = Desugar(Vec::new());
+    for arg in sig.inputs.iter_mut() {
+        if let FnArg::Typed(PatType { ty, .. }) = arg {
+            desugar.visit_type_mut(ty);
+        }
+    }
+    sig.generics
+        .params
+        .extend(desugar.0.into_iter().map(GenericParam::Type));
+
+    // Where clauses to bounds of generic parameters.
+    if let Some(where_clause) = sig.generics.where_clause.take() {
+        let mut remains = where_clause.clone();
+        remains.predicates.clear();
+        for pred in where_clause.predicates {
+            let param = match &pred {
+                WherePredicate::Type(PredicateType {
+                    lifetimes: None,
+                    bounded_ty: Type::Path(TypePath { qself: None, path }),
+                    ..
+                }) => path.get_ident().and_then(|ident| {
+                    sig.generics
+                        .type_params_mut()
+                        .find(|param| &param.ident == ident)
+                }),
+                _ => None,
+            };
+            match (param, pred) {
+                (Some(param), WherePredicate::Type(PredicateType { bounds, .. })) => {
+                    if !param.bounds.is_empty() && !param.bounds.trailing_punct() {
+                        param.bounds.push_punct(Default::default());
+                    }
+                    param.colon_token.get_or_insert_with(Default::default);
+                    param.bounds.extend(bounds);
+                }
+                (_, pred) => remains.predicates.push(pred),
+            }
+        }
+        if !remains.predicates.is_empty() {
+            sig.generics.where_clause = Some(remains);
+        }
+    }
+
+    sig
+}
+
+/// Generates turbofish like `::<T>` for the given `Generics`.
+/// Parameters made from `impl Trait` arguments are excluded because they can't be specified.
+#[allow(dead_code)]
+pub fn gen_turbofish(generics: &Generics) -> TokenStream2 {
+    let params = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(TypeParam { ident, .. }) => Some(ident),
+            GenericParam::Const(ConstParam { ident, .. }) => Some(ident),
+            GenericParam::Lifetime(..) => None,
+        })
+        .filter(|ident| !is_impl_trait_param(ident))
+        .collect::<Vec<_>>();
+    if params.is_empty() {
+        quote! {}
+    } else {
+        quote! { ::<#(#params),*> }
+    }
+}
+
+/// Determines that the given generic parameter is made from `impl Trait` argument.
+#[allow(dead_code)]
+pub fn is_impl_trait_param(ident: &Ident) -> bool {
+    ident.to_string().starts_with(IMPL_TRAIT_PREFIX)
+}
+
+/// Checks that the given `Signature` can be erased.
+#[allow(dead_code)]
+pub fn validate_signature(sig: &Signature) -> Result<()> {
+    // Validates each argument.
+    let sig = &normalize_generics(sig);
+    let mut erased_params =
+        sig.generics.params.iter().filter(|param| {
+            !matches!(param, GenericParam::Lifetime(..)) && !is_callback_param(param)
+        });
+    if is_generic(sig) && erased_params.next().is_none() {
+        return Err(Error::new_spanned(
+            &sig.generics,
+            "methods only generic over callbacks are not supported for now; \
+            take the callback as `&mut dyn FnMut(..)` instead",
+        ));
+    }
+    if let Some(param) = erased_params.next() {
+        return Err(Error::new_spanned(
+            param,
+            "only one generic parameter per method is supported for now; \
+            split the method so that each one takes a single generic parameter",
+        ));
+    }
+    for arg in sig.inputs.iter() {
+        parse_arg(arg)?;
+        change_arg_to_any(&mut arg.clone(), &sig.generics)?;
+    }
+
+    // Validates the return type.
+    if let Some((output, _)) = get_future_output(sig) {
+        if let Some(lifetime) = sig.generics.lifetimes().next() {
+            return Err(Error::new_spanned(
+                lifetime,
+                "async methods with lifetime parameters are not supported for now; \
+                use elided lifetimes instead",
+            ));
+        }
+        let symbols = get_generic_symbols(&sig.generics)?;
+        if mentions_any(&output, symbols.iter().map(|s| s.as_str())) {
+            return Err(Error::new_spanned(
+                output,
+                "async methods returning generic types are not supported for now; \
+                write the result into a `&mut` argument instead",
+            ));
+        }
+    } else {
+        get_erased_output(sig)?;
+    }
+    Ok(())
+}
+
+/// Lifetime of boxed futures returned from erased async methods.
+const ASYNC_LIFETIME: &str = "'__async";
+
+/// Gets the output type of the future if the method is async, and whether the future is `Send`.
+/// Async methods are `async fn` or `fn` returning `impl Future<Output = T>`.
+#[allow(dead_code)]
+pub fn get_future_output(sig: &Signature) -> Option<(Type, bool)> {
+    if sig.asyncness.is_some() {
+        let output = match &sig.output {
+            ReturnType::Default => parse_quote! { () },
+            ReturnType::Type(_, ty) => ty.as_ref().clone(),
+        };
+        return Some((output, false));
+    }
+
+    let ReturnType::Type(_, ty) = &sig.output else {
+        return None;
+    };
+    let Type::ImplTrait(TypeImplTrait { bounds, .. }) = ty.as_ref() else {
+        return None;
+    };
+    let mut output = None;
+    let mut is_send = false;
+    for bound in bounds {
+        let TypeParamBound::Trait(TraitBound { path, .. }) = bound else {
+            continue;
+        };
+        let Some(seg) = path.segments.last() else {
+            continue;
+        };
+        if seg.ident == "Send" {
+            is_send = true;
+        } else if let (true, PathArguments::AngleBracketed(args)) =
+            (seg.ident == "Future", &seg.arguments)
+        {
+            output = args.args.iter().find_map(|arg| match arg {
+                GenericArgument::AssocType(AssocType { ident, ty, .. }) if ident == "Output" => {
+                    Some(ty.clone())
+                }
+                _ => None,
+            });
+        }
+    }
+    output.map(|output| (output, is_send))
+}
+
+/// Changes the async method into a method returning `Pin<Box<dyn Future>>`,
+/// which lives as long as all references in the arguments.
+/// Does nothing if the method is not async.
+#[allow(dead_code)]
+pub fn into_boxed_future(sig: &mut Signature) {
+    let Some((output, is_send)) = get_future_output(sig) else {
+        return;
+    };
+
+    // All elided lifetimes in the arguments become the lifetime of the future.
+    let lifetime = Lifetime::new(ASYNC_LIFETIME, Span::call_site());
+    let mut namer = LifetimeNamer {
+        lifetimes: Vec::new(),
+        output_lifetime: Some(lifetime.clone()),
+    };
+    for arg in sig.inputs.iter_mut() {
+        match arg {
+            FnArg::Receiver(receiver) => {
+                if let Some((_, elided @ None)) = receiver.reference.as_mut() {
+                    *elided = Some(lifetime.clone());
+                }
+                namer.visit_type_mut(&mut receiver.ty);
+            }
+            FnArg::Typed(PatType { ty, .. }) => namer.visit_type_mut(ty),
+        }
+    }
+    sig.asyncness = None;
+    sig.generics.params.insert(
+        0,
+        GenericParam::Lifetime(LifetimeParam::new(lifetime.clone())),
+    );
+    sig.generics.lt_token.get_or_insert_with(Default::default);
+    sig.generics.gt_token.get_or_insert_with(Default::default);
+
+    let send = is_send.then(|| quote! { + Send });
+    sig.output = parse_quote! {
+        -> std::pin::Pin<std::boxed::Box<
+            dyn std::future::Future<Output = #output> #send + #lifetime
+        >>
+    };
+}
+
+/// Name of the injected parameter for const generic methods.
+#[allow(dead_code)]
+pub const CONST_KEY: &str = "__const_key__";
+
+/// Gets the generic parameter to be erased, which is the first type or const parameter.
+/// Callback parameters are not the one because they are erased into trait objects.
+#[allow(dead_code)]
+pub fn get_erased_param(generics: &Generics) -> Option<&GenericParam> {
+    generics
+        .params
+        .iter()
+        .find(|param| !matches!(param, GenericParam::Lifetime(..)) && !is_callback_param(param))
+}
+
+/// Generates the key parameter for the given `Generics`.
+/// It's `__type_id__: &TypeId` for generic types, and `__const_key__` that has the value
+/// for const generics, e.g. `__const_key__: usize` for `const N: usize`.
+#[allow(dead_code)]
+pub fn gen_key_arg(generics: &Generics) -> FnArg {
+    let const_key = gen_ident(CONST_KEY);
+    match get_erased_param(generics) {
+        Some(GenericParam::Const(ConstParam { ty, .. })) => parse_quote! { #const_key: #ty },
+        _ => parse_quote! { __type_id__: &std::any::TypeId },
+    }
+}
+
+/// Injects the key parameter as the second parameter.
+#[allow(dead_code)]
+pub fn inject_key(sig: &mut Signature, key: FnArg) {
+    sig.inputs.insert(1, key);
 }
 
 /// Removes `Generics`.
@@ -252,31 +1204,89 @@ pub fn remove_generics(generics: &mut Generics) {
     }
 }
 
+/// Merges generic parameters and where clauses of the outer item, like the implementor struct,
+/// into the inner ones, so that nested functions can use them.
+/// Lifetimes come first, and defaults are removed because functions can't have them.
+#[allow(dead_code)]
+pub fn merge_generics(outer: &Generics, inner: &Generics) -> Generics {
+    let params = outer.params.iter().chain(inner.params.iter());
+    let lifetimes = params
+        .clone()
+        .filter(|param| matches!(param, GenericParam::Lifetime(..)));
+    let others = params.filter(|param| !matches!(param, GenericParam::Lifetime(..)));
+    let params = lifetimes
+        .chain(others)
+        .cloned()
+        .map(|mut param| {
+            match &mut param {
+                GenericParam::Type(param) => {
+                    param.eq_token = None;
+                    param.default = None;
+                }
+                GenericParam::Const(param) => {
+                    param.eq_token = None;
+                    param.default = None;
+                }
+                GenericParam::Lifetime(..) => {}
+            }
+            param
+        })
+        .collect();
+    let predicates = outer
+        .where_clause
+        .iter()
+        .chain(inner.where_clause.iter())
+        .flat_map(|where_clause| where_clause.predicates.iter().cloned())
+        .collect::<punctuated::Punctuated<_, Token![,]>>();
+    Generics {
+        lt_token: Some(Default::default()),
+        params,
+        gt_token: Some(Default::default()),
+        where_clause: (!predicates.is_empty()).then(|| parse_quote! { where #predicates }),
+    }
+}
+
+/// Gets type and const generic arguments in order without lifetimes, like `T, N` from
+/// `<'a, T, const N: usize>`, so that they can be given by turbofish.
+#[allow(dead_code)]
+pub fn get_generic_args(generics: &Generics) -> Vec<&Ident> {
+    generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(TypeParam { ident, .. }) => Some(ident),
+            GenericParam::Const(ConstParam { ident, .. }) => Some(ident),
+            GenericParam::Lifetime(..) => None,
+        })
+        .collect()
+}
+
 /// Gets `Ident`s from the list of `FnArg`.
 #[allow(dead_code)]
-pub fn get_idents(args: &punctuated::Punctuated<FnArg, Token![,]>) -> Vec<Ident> {
-    args.iter().map(|arg| get_ident(arg).clone()).collect()
+pub fn get_idents(args: &punctuated::Punctuated<FnArg, Token![,]>) -> Result<Vec<Ident>> {
+    args.iter().map(|arg| get_ident(arg).cloned()).collect()
 }
 
 /// Gets a `Ident` from the `FnArg`.
 #[allow(dead_code)]
-pub fn get_ident(arg: &FnArg) -> &Ident {
-    let (ident, _, _) = parse_arg(arg);
-    ident
+pub fn get_ident(arg: &FnArg) -> Result<&Ident> {
+    let (ident, _, _) = parse_arg(arg)?;
+    Ok(ident)
 }
 
 /// Gets matched `Ident`s with the given nth generic parameter from the list of `FnArg`.
 #[allow(dead_code)]
-pub fn get_nth_ident(sig: &Signature, n: usize) -> Vec<Ident> {
-    let symbols = get_generic_symbols(&sig.generics);
+pub fn get_nth_ident(sig: &Signature, n: usize) -> Result<Vec<Ident>> {
+    let symbols = get_generic_symbols(&sig.generics)?;
     let symbol = symbols.into_iter().nth(n);
     if let Some(symbol) = symbol {
-        sig.inputs
+        Ok(sig
+            .inputs
             .iter()
             .filter_map(|arg| get_matched_ident(arg, symbol.as_str()).cloned())
-            .collect()
+            .collect())
     } else {
-        Vec::new()
+        Ok(Vec::new())
     }
 }
 
@@ -298,6 +1308,23 @@ pub fn camel_case(snake_case: &str) -> String {
     res
 }
 
+/// Generates snake case string from the given camel case str.
+#[allow(dead_code)]
+pub fn snake_case(camel_case: &str) -> String {
+    let mut res = String::new();
+    for (i, c) in camel_case.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                res.push('_');
+            }
+            res.push(c.to_ascii_lowercase());
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
+
 /// Gets the nth `GenericParam` from the given `Signature`s.
 #[allow(dead_code)]
 pub fn get_nth_generic(sig: &Signature, n: usize) -> Option<&GenericParam> {
@@ -305,9 +1332,17 @@ pub fn get_nth_generic(sig: &Signature, n: usize) -> Option<&GenericParam> {
 }
 
 /// Determines that the given `Signature` is generic.
+/// `impl Trait` arguments are also generic, but lifetime parameters are not.
 #[allow(dead_code)]
 pub fn is_generic(sig: &Signature) -> bool {
-    get_nth_generic(sig, 0).is_some()
+    sig.generics
+        .params
+        .iter()
+        .any(|param| !matches!(param, GenericParam::Lifetime(..)))
+        || sig.inputs.iter().any(|arg| match arg {
+            FnArg::Typed(PatType { ty, .. }) => mentions_any(ty, std::iter::once("impl")),
+            FnArg::Receiver(..) => false,
+        })
 }
 
 /// Determines that the given `FnArg` is a reference of Any.
@@ -318,7 +1353,7 @@ pub fn is_any(arg: &FnArg) -> bool {
         if let Some(bound) = bounds.iter().next() {
             let path = match bound {
                 TypeParamBound::Trait(TraitBound { path, .. }) => path,
-                _ => unimplemented!(),
+                _ => return false,
             };
             let last_ident = path.segments.last().unwrap().ident.to_string();
             &last_ident == "Any"
@@ -330,8 +1365,231 @@ pub fn is_any(arg: &FnArg) -> bool {
     }
 }
 
-/// Determines that the given `Signature` is associated function.
+/// Determines that the given `Signature` is associated function, which doesn't have a receiver.
+#[allow(dead_code)]
+pub fn is_associated_function(sig: &Signature) -> bool {
+    !matches!(sig.inputs.first(), Some(FnArg::Receiver(..)))
+}
+
+/// Determines that the given method can't be called on trait objects.
+/// They are associated functions, methods taking or returning `Self`,
+/// and methods bounded by `where Self: Sized`.
+#[allow(dead_code)]
+pub fn is_sized_only(sig: &Signature) -> bool {
+    let mentions_self = sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(PatType { ty, .. }) => Some(ty.as_ref()),
+            FnArg::Receiver(..) => None,
+        })
+        .chain(match &sig.output {
+            ReturnType::Type(_, ty) => Some(ty.as_ref()),
+            ReturnType::Default => None,
+        })
+        .any(mentions_self_type);
+    is_associated_function(sig) || mentions_self || has_sized_self_bound(sig)
+}
+
+/// Determines that the given `Signature` has `where Self: Sized`.
+#[allow(dead_code)]
+pub fn has_sized_self_bound(sig: &Signature) -> bool {
+    let Some(where_clause) = &sig.generics.where_clause else {
+        return false;
+    };
+    where_clause.predicates.iter().any(|pred| match pred {
+        WherePredicate::Type(PredicateType {
+            bounded_ty: Type::Path(TypePath { qself: None, path }),
+            bounds,
+            ..
+        }) if path.is_ident("Self") => bounds.iter().any(|bound| {
+            matches!(
+                bound,
+                TypeParamBound::Trait(TraitBound {
+                    modifier: TraitBoundModifier::None,
+                    path,
+                    ..
+                }) if path.is_ident("Sized")
+            )
+        }),
+        _ => false,
+    })
+}
+
+/// Adds `where Self: Sized` to the given method if it doesn't have yet.
+#[allow(dead_code)]
+pub fn add_sized_self_bound(sig: &mut Signature) {
+    if !has_sized_self_bound(sig) {
+        sig.generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote! { Self: Sized });
+    }
+}
+
+/// Determines that the given `Type` contains `Self` itself, not paths like `Self::Assoc`.
+fn mentions_self_type(ty: &Type) -> bool {
+    struct Finder(bool);
+
+    impl VisitMut for Finder {
+        fn visit_type_path_mut(&mut self, ty: &mut TypePath) {
+            if ty.qself.is_none() && ty.path.is_ident("Self") {
+                self.0 = true;
+            }
+            visit_mut::visit_type_path_mut(self, ty);
+        }
+    }
+
+    let mut finder = Finder(false);
+    finder.visit_type_mut(&mut ty.clone());
+    finder.0
+}
+
+/// Gives names to elided lifetimes in the given `Type`s.
+/// Elided lifetimes in inputs get fresh names, and ones in output get `output_lifetime` if exists,
+/// which follows lifetime elision rules of methods.
+#[allow(dead_code)]
+struct LifetimeNamer {
+    lifetimes: Vec<Lifetime>,
+    output_lifetime: Option<Lifetime>,
+}
+
+impl LifetimeNamer {
+    fn next(&mut self) -> Lifetime {
+        if let Some(lifetime) = self.output_lifetime.as_ref() {
+            return lifetime.clone();
+        }
+        let lifetime = Lifetime::new(
+            &format!("'__life{}", self.lifetimes.len()),
+            Span::call_site(),
+        );
+        self.lifetimes.push(lifetime.clone());
+        lifetime
+    }
+}
+
+impl VisitMut for LifetimeNamer {
+    fn visit_type_reference_mut(&mut self, ty: &mut TypeReference) {
+        if ty.lifetime.is_none() {
+            ty.lifetime = Some(self.next());
+        }
+        visit_mut::visit_type_reference_mut(self, ty);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = self.next();
+        }
+    }
+
+    // Function types have their own elision rules.
+    fn visit_type_bare_fn_mut(&mut self, _: &mut TypeBareFn) {}
+    fn visit_parenthesized_generic_arguments_mut(&mut self, _: &mut ParenthesizedGenericArguments) {
+    }
+}
+
+/// Gets input and output types of the given `Signature` as if it was a function pointer,
+/// which receives `self_ty` instead of `self`. Associated types are resolved through `self_trait`.
+/// Elided lifetimes become named lifetimes, which are returned together.
+#[allow(dead_code)]
+pub fn get_fn_pointer_types(
+    sig: &Signature,
+    self_ty: &Type,
+    self_trait: &Path,
+) -> (Vec<Lifetime>, Vec<Type>, Type) {
+    let mut namer = LifetimeNamer {
+        lifetimes: Vec::new(),
+        output_lifetime: None,
+    };
+
+    let mut inputs = Vec::new();
+    let mut self_lifetime = None;
+    for arg in sig.inputs.iter() {
+        let mut ty = match arg {
+            FnArg::Receiver(Receiver {
+                reference: Some((_, lifetime)),
+                mutability,
+                ..
+            }) => {
+                let lifetime = lifetime.clone().unwrap_or_else(|| namer.next());
+                self_lifetime = Some(lifetime.clone());
+                parse_quote! { &#lifetime #mutability #self_ty }
+            }
+            // `Pin<&mut Self>` gives its lifetime to the output like `&mut self`.
+            FnArg::Receiver(Receiver { ty, .. }) => {
+                let mut ty = ty.as_ref().clone();
+                namer.visit_type_mut(&mut ty);
+                self_lifetime = get_first_lifetime(&mut ty);
+                ty
+            }
+            FnArg::Typed(PatType { ty, .. }) => {
+                let mut ty = ty.as_ref().clone();
+                namer.visit_type_mut(&mut ty);
+                ty
+            }
+        };
+        replace_self_type(&mut ty, self_ty, self_trait);
+        inputs.push(ty);
+    }
+
+    namer.output_lifetime = self_lifetime;
+    let mut output = match &sig.output {
+        ReturnType::Default => parse_quote! { () },
+        ReturnType::Type(_, ty) => ty.as_ref().clone(),
+    };
+    namer.visit_type_mut(&mut output);
+    replace_self_type(&mut output, self_ty, self_trait);
+
+    (namer.lifetimes, inputs, output)
+}
+
+/// Gets the first lifetime in the given `Type`.
+#[allow(dead_code)]
+pub fn get_first_lifetime(ty: &mut Type) -> Option<Lifetime> {
+    struct Finder(Option<Lifetime>);
+
+    impl VisitMut for Finder {
+        fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+            self.0.get_or_insert_with(|| lifetime.clone());
+        }
+    }
+
+    let mut finder = Finder(None);
+    finder.visit_type_mut(ty);
+    finder.0
+}
+
+/// Replaces `Self` types in the given `Type` with `self_ty`.
+/// Associated types like `Self::Output` become `<SelfTy as SelfTrait>::Output`.
+#[allow(dead_code)]
+pub fn replace_self_type(ty: &mut Type, self_ty: &Type, self_trait: &Path) {
+    struct Replacer<'a>(&'a Type, &'a Path);
+
+    impl VisitMut for Replacer<'_> {
+        fn visit_type_mut(&mut self, ty: &mut Type) {
+            match ty {
+                Type::Path(TypePath { qself: None, path }) if path.is_ident("Self") => {
+                    *ty = self.0.clone();
+                }
+                Type::Path(TypePath { qself: None, path })
+                    if path.segments.len() > 1 && path.segments[0].ident == "Self" =>
+                {
+                    let (self_ty, self_trait) = (self.0, self.1);
+                    let assoc = path.segments.iter().skip(1);
+                    *ty = parse_quote! { <#self_ty as #self_trait> #(::#assoc)* };
+                }
+                _ => visit_mut::visit_type_mut(self, ty),
+            }
+        }
+    }
+
+    Replacer(self_ty, self_trait).visit_type_mut(ty);
+}
+
+/// Generates the name of the accessor method for the associated constant,
+/// e.g. `erased_const_kind` for `KIND`.
 #[allow(dead_code)]
-pub fn is_associated_function(sig: Signature) -> bool {
-    matches!(sig.inputs.first(), Some(&FnArg::Receiver(..)))
+pub fn gen_const_accessor_ident(ident: &Ident) -> Ident {
+    let name = format!("erased_const_{}", ident.to_string().to_lowercase());
+    clone_ident_with_name(ident, &name)
 }
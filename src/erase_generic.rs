@@ -1,114 +1,586 @@
 use crate::common::*;
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
-use syn::{parse_macro_input, ItemTrait};
-use syn::{Signature, TraitItem, TraitItemFn};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parenthesized, parse_macro_input, Ident, ItemTrait, Path, Result, Token};
+use syn::{
+    parse_quote, ConstParam, GenericParam, LifetimeParam, PathArguments, Signature, TraitBound,
+    TraitItem, TraitItemConst, TraitItemFn, TraitItemType, Type, TypeParam, TypeParamBound,
+    TypePath, TypeReference, WherePredicate,
+};
 
 /// Generates a new trait without generic parameters.
 /// Then implements input trait for the new trait object.
 pub fn erase_generic(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let erased_name = attr.to_string();
+    let attr = parse_macro_input!(attr as Attr);
     let src_trait = parse_macro_input!(item as ItemTrait);
-    let mut erased_trait = src_trait.clone();
 
-    // Makes new trait with the name of `erased_trait_name`.
-    into_erased_generic(&mut erased_trait, erased_name.as_str());
+    expand(attr, src_trait)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Attribute of `erase_generic` macro.
+/// Looks like `ErasedGeneric`, `ErasedSink, erase(T)` or
+/// `ErasedDerived, supertrait(Base = ErasedBase)`.
+struct Attr {
+    erased_ident: Ident,
+    /// Type parameter of the trait to be erased like generic parameters of methods.
+    erased_param: Option<Ident>,
+    /// Supertraits that are erased as well.
+    supertraits: Vec<Supertrait>,
+}
+
+/// Supertrait and its erased trait like `Base = ErasedBase`.
+struct Supertrait {
+    src_path: Path,
+    erased_path: Path,
+}
+
+impl Parse for Supertrait {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let src_path = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let erased_path = input.parse()?;
+        Ok(Self {
+            src_path,
+            erased_path,
+        })
+    }
+}
+
+impl Parse for Attr {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let erased_ident = input.parse()?;
+        let mut erased_param = None;
+        let mut supertraits = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let option = input.parse::<Ident>()?;
+            let content;
+            parenthesized!(content in input);
+            if option == "supertrait" {
+                let parsed = Punctuated::<Supertrait, Token![,]>::parse_terminated(&content)?;
+                supertraits.extend(parsed);
+                continue;
+            }
+            if option != "erase" {
+                return Err(syn::Error::new_spanned(
+                    option,
+                    "unsupported option; expected `erase(T)` or `supertrait(Base = ErasedBase)`",
+                ));
+            }
+            let param = content.parse::<Ident>()?;
+            if !content.is_empty() || erased_param.is_some() {
+                return Err(syn::Error::new_spanned(
+                    param,
+                    "only one type parameter of the trait can be erased",
+                ));
+            }
+            erased_param = Some(param);
+        }
+        Ok(Self {
+            erased_ident,
+            erased_param,
+            supertraits,
+        })
+    }
+}
+
+/// Expands `erase_generic` macro.
+fn expand(attr: Attr, mut src_trait: ItemTrait) -> Result<TokenStream2> {
+    let Attr {
+        erased_ident,
+        erased_param,
+        supertraits,
+    } = attr;
+
+    // Methods that can't be called on trait objects or skipped by `#[erased(skip)]` are bounded
+    // by `Self: Sized`, so that the generic trait can be implemented for the trait object
+    // without them. The bound changes the generic trait, so it's noted in the docs.
+    let mut renames = Vec::new();
+    let mut left_out = Vec::new();
+    for item in src_trait.items.iter_mut() {
+        if let TraitItem::Fn(TraitItemFn { attrs, sig, .. }) = item {
+            let options = ErasedOptions::take(attrs)?;
+            if options.skip || is_sized_only(sig) {
+                if !has_sized_self_bound(sig) {
+                    let note = format!(
+                        "`where Self: Sized` is added by `erase_generic`, \
+                        so this method is left out of `{erased_ident}`."
+                    );
+                    attrs.push(parse_quote! { #[doc = ""] });
+                    attrs.push(parse_quote! { #[doc = #note] });
+                    add_sized_self_bound(sig);
+                }
+                left_out.push(format!("`{}`", sig.ident));
+            } else {
+                renames.push(options.rename);
+            }
+        }
+    }
+
+    // Erased type parameter of the trait is moved onto methods, then erased with them.
+    let mut lifted_trait = src_trait.clone();
+    if let Some(param) = erased_param.as_ref() {
+        lift_trait_param(&mut lifted_trait, param)?;
+    }
+    let mut erased_trait = lifted_trait.clone();
+
+    // Makes new trait with the name of `erased_ident`.
+    into_erased_generic(&mut erased_trait, erased_ident, &renames)?;
+    map_supertraits(&mut erased_trait, &supertraits);
+    if !left_out.is_empty() {
+        let note = format!(
+            "Left out methods of `{}`, which are bounded by `where Self: Sized`: {}.",
+            src_trait.ident,
+            left_out.join(", ")
+        );
+        erased_trait.attrs.push(parse_quote! { #[doc = ""] });
+        erased_trait.attrs.push(parse_quote! { #[doc = #note] });
+    }
 
     // Makes impl of dyn erased generic trait.
-    let generic_for_dyn_erased = impl_generic_for_dyn_erased(&src_trait, &erased_trait);
+    let generic_for_dyn_erased =
+        impl_generic_for_dyn_erased(&src_trait, &lifted_trait, &erased_trait, &supertraits)?;
 
-    quote! {
+    Ok(quote! {
         #src_trait
         #erased_trait
         #generic_for_dyn_erased
+    })
+}
+
+/// Moves the type parameter of the trait onto its methods, like `fn push<T: Bound>(&mut self, t: &T)`
+/// from `trait Sink<T: Bound>`, so that the parameter can be erased as generic parameters of
+/// methods are.
+fn lift_trait_param(ast: &mut ItemTrait, param: &Ident) -> Result<()> {
+    let Some(mut type_param) = ast.generics.params.iter().find_map(|p| match p {
+        GenericParam::Type(type_param) if &type_param.ident == param => Some(type_param.clone()),
+        _ => None,
+    }) else {
+        return Err(syn::Error::new_spanned(
+            param,
+            "expected a type parameter of the trait",
+        ));
+    };
+    type_param.eq_token = None;
+    type_param.default = None;
+    ast.generics.params = ast
+        .generics
+        .params
+        .iter()
+        .filter(|p| !matches!(p, GenericParam::Type(TypeParam { ident, .. }) if ident == param))
+        .cloned()
+        .collect();
+
+    // Where predicates on the parameter go along with it.
+    let name = param.to_string();
+    let mut predicates = Vec::<WherePredicate>::new();
+    if let Some(where_clause) = ast.generics.where_clause.as_mut() {
+        let (moved, kept) = where_clause
+            .predicates
+            .iter()
+            .cloned()
+            .partition::<Vec<_>, _>(|pred| mentions_any(pred, std::iter::once(name.as_str())));
+        predicates = moved;
+        where_clause.predicates = kept.into_iter().collect();
+    }
+
+    for item in ast.items.iter_mut() {
+        let TraitItem::Fn(TraitItemFn { sig, .. }) = item else {
+            continue;
+        };
+        if is_sized_only(sig) {
+            continue;
+        }
+        // The parameter is the key to find out the function, so methods must take it.
+        if !mentions_any(&sig.inputs, std::iter::once(name.as_str())) {
+            return Err(syn::Error::new_spanned(
+                &sig.ident,
+                format!(
+                    "methods must take `{name}` in their arguments to erase it; \
+                    skip this method by `#[erased(skip)]`"
+                ),
+            ));
+        }
+        sig.generics
+            .params
+            .push(GenericParam::Type(type_param.clone()));
+        if !predicates.is_empty() {
+            sig.generics
+                .make_where_clause()
+                .predicates
+                .extend(predicates.iter().cloned());
+        }
     }
-    .into()
+    Ok(())
 }
 
 /// Makes generic methods become non-generic.
-fn into_erased_generic(ast: &mut ItemTrait, new_name: &str) {
+/// Erased methods are renamed to the given names if exist.
+fn into_erased_generic(
+    ast: &mut ItemTrait,
+    new_ident: Ident,
+    renames: &[Option<Ident>],
+) -> Result<()> {
     // Modifies the trait name.
-    modify_ident(&mut ast.ident, new_name);
+    ast.ident = new_ident;
+
+    // Leaves out methods that can't be called on trait objects.
+    ast.items.retain(
+        |item| !matches!(item, TraitItem::Fn(TraitItemFn { sig, .. }) if is_sized_only(sig)),
+    );
+
+    // Associated constants make trait objects impossible,
+    // so they are replaced with accessor methods like `fn erased_const_kind(&self) -> u8`.
+    let mut accessors = Vec::new();
+    for item in ast.items.iter() {
+        match item {
+            TraitItem::Const(TraitItemConst { ident, ty, .. }) => {
+                let accessor_ident = gen_const_accessor_ident(ident);
+                accessors.push(parse_quote! { fn #accessor_ident(&self) -> #ty; });
+            }
+            // Generic associated types make trait objects impossible as well.
+            TraitItem::Type(TraitItemType { generics, .. }) if !generics.params.is_empty() => {
+                return Err(syn::Error::new_spanned(
+                    generics,
+                    "generic associated types are not supported; \
+                    move this type into a separate trait that isn't erased",
+                ));
+            }
+            _ => {}
+        }
+    }
+    ast.items
+        .retain(|item| !matches!(item, TraitItem::Const(..)));
+
+    // Default bodies are kept in the generic trait, and the erased trait only declares methods.
+    // Implementors forward them to the generic trait, so that the default bodies are called.
+    for item in ast.items.iter_mut() {
+        if let TraitItem::Fn(item) = item {
+            if item.default.take().is_some() {
+                item.semi_token = Some(Default::default());
+            }
+        }
+    }
 
     // Gets signatures.
     let sigs = get_signatures(ast);
 
     // Tries to change signatures.
-    for sig in sigs {
-        modify_signature_to_erased(sig);
+    for (sig, rename) in sigs.into_iter().zip(renames) {
+        modify_signature_to_erased(sig)?;
+        if let Some(rename) = rename {
+            sig.ident = rename.clone();
+        }
+    }
+
+    // Accessors come after the methods, so that the methods keep the order of the generic trait.
+    ast.items.extend(accessors);
+    Ok(())
+}
+
+/// Replaces supertraits with their erased traits like `ErasedDerived: ErasedBase`,
+/// because the generic supertraits make trait objects impossible.
+/// Arguments of the supertrait are carried onto the erased one unless it's given with arguments.
+fn map_supertraits(ast: &mut ItemTrait, supertraits: &[Supertrait]) {
+    for bound in ast.supertraits.iter_mut() {
+        let TypeParamBound::Trait(TraitBound { path, .. }) = bound else {
+            continue;
+        };
+        let Some(last) = path.segments.last() else {
+            continue;
+        };
+        let Some(supertrait) = supertraits.iter().find(|supertrait| {
+            supertrait
+                .src_path
+                .segments
+                .last()
+                .is_some_and(|seg| seg.ident == last.ident)
+        }) else {
+            continue;
+        };
+        let mut erased_path = supertrait.erased_path.clone();
+        if let Some(erased_last) = erased_path.segments.last_mut() {
+            if erased_last.arguments.is_empty() {
+                erased_last.arguments = last.arguments.clone();
+            }
+        }
+        *path = erased_path;
     }
 }
 
-/// Gets `Signature`s from the `ItemTrait`.
+/// Generates the name of the marker trait for trait objects, like `DynErasedGeneric`.
+/// The generic trait is implemented for trait objects having the marker,
+/// so that trait objects of erased subtraits can implement the generic trait as well.
+fn gen_marker_path(erased_path: &Path) -> Path {
+    let mut path = erased_path.clone();
+    if let Some(last) = path.segments.last_mut() {
+        last.ident = clone_ident_with_prefix(&last.ident, "Dyn");
+        last.arguments = PathArguments::None;
+    }
+    path
+}
+
+/// Gets `Signature`s from the `ItemTrait` except ones that can't be called on trait objects.
 fn get_signatures(ast: &mut ItemTrait) -> Vec<&mut Signature> {
     ast.items
         .iter_mut()
         .filter_map(|it| match it {
-            TraitItem::Fn(TraitItemFn { sig, .. }) => Some(sig),
+            TraitItem::Fn(TraitItemFn { sig, .. }) if !is_sized_only(sig) => Some(sig),
             _ => None,
         })
         .collect()
 }
 
 /// impl generic for dyn erased.
-fn impl_generic_for_dyn_erased(src: &ItemTrait, erased: &ItemTrait) -> TokenStream2 {
+/// `lifted` is the source trait whose erased type parameter is moved onto methods.
+/// The generic trait is implemented for any trait objects having the marker trait,
+/// like `impl<__Dyn: ?Sized + ErasedGeneric + DynErasedGeneric> Generic for __Dyn`,
+/// so that trait objects of erased subtraits implement it as well.
+fn impl_generic_for_dyn_erased(
+    src: &ItemTrait,
+    lifted: &ItemTrait,
+    erased: &ItemTrait,
+    supertraits: &[Supertrait],
+) -> Result<TokenStream2> {
     // Gets source trait name.
     let src_trait_ident = &src.ident;
 
     // Gets erased trait name.
     let erased_trait_ident = &erased.ident;
 
+    // Associated types are given to the trait object like `dyn ErasedGeneric<Output = __Output>`.
+    // Generic parameters of the trait are carried onto the impl as well,
+    // like `impl<K: Key> Storage<K> for dyn ErasedStorage<K>`.
+    // Erased type parameter of the trait is not given to the trait object,
+    // like `impl<T: Bound> Sink<T> for dyn ErasedSink`.
+    let mut generics = src.generics.clone();
+    let mut lifted_generics = lifted.generics.clone();
+    let mut erased_args = lifted
+        .generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Type(TypeParam { ident, .. }) => quote! { #ident },
+            GenericParam::Const(ConstParam { ident, .. }) => quote! { #ident },
+            GenericParam::Lifetime(LifetimeParam { lifetime, .. }) => quote! { #lifetime },
+        })
+        .collect::<Vec<_>>();
+    let mut assoc_idents = Vec::new();
+    let mut assoc_param_idents = Vec::new();
+    for item in src.items.iter() {
+        if let TraitItem::Type(TraitItemType { ident, bounds, .. }) = item {
+            let param_ident = format_ident!("__{ident}");
+            generics.params.push(parse_quote! { #param_ident: #bounds });
+            lifted_generics
+                .params
+                .push(parse_quote! { #param_ident: #bounds });
+            erased_args.push(quote! { #ident = #param_ident });
+            assoc_idents.push(ident);
+            assoc_param_idents.push(param_ident);
+        }
+    }
+    let erased_args = if erased_args.is_empty() {
+        quote! {}
+    } else {
+        quote! { <#(#erased_args),*> }
+    };
+
+    // Trait objects of the erased trait and erased subtraits get the marker traits.
+    // Erased type parameter of the trait is not a parameter of the marker implementations.
+    // Trait objects may borrow like `dyn ErasedGeneric + 'a`, so any lifetime is accepted.
+    let vis = &src.vis;
+    lifted_generics.params.insert(0, parse_quote! { '__dyn });
+    let marker_path = gen_marker_path(&parse_quote! { #erased_trait_ident });
+    let super_marker_paths = supertraits
+        .iter()
+        .map(|supertrait| gen_marker_path(&supertrait.erased_path))
+        .collect::<Vec<_>>();
+    let (impl_generics, _, where_clause) = lifted_generics.split_for_impl();
+    let ptr_path = gen_ptr_path(&parse_quote! { #erased_trait_ident });
+    let ptr_mod = format_ident!("__{}_ptr", snake_case(&erased_trait_ident.to_string()));
+    let markers = quote! {
+        #[doc(hidden)]
+        #vis trait #marker_path {}
+
+        impl #impl_generics #marker_path
+        for dyn #erased_trait_ident #erased_args + '__dyn #where_clause {}
+        #(
+            impl #impl_generics #super_marker_paths
+            for dyn #erased_trait_ident #erased_args + '__dyn #where_clause {}
+        )*
+
+        // Unsized arguments are passed as pointers in `&dyn Any`.
+        // The field is private to the module, so callers can't make the pointer type
+        // without `new`, and erased methods never dereference raw pointers from the callers.
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        mod #ptr_mod {
+            pub struct #ptr_path<P>(P);
+
+            impl<P: Copy> #ptr_path<P> {
+                /// # Safety
+                ///
+                /// The pointer must come from a reference that outlives this.
+                pub unsafe fn new(ptr: P) -> Self {
+                    Self(ptr)
+                }
+
+                pub fn get(&self) -> P {
+                    self.0
+                }
+            }
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        #vis use #ptr_mod::#ptr_path;
+    };
+
+    let mut dyn_generics = generics.clone();
+    dyn_generics.params.push(parse_quote! {
+        __Dyn: ?Sized + #erased_trait_ident #erased_args + #marker_path #(+ #super_marker_paths)*
+    });
+    let (impl_generics, _, where_clause) = dyn_generics.split_for_impl();
+    let (_, src_args, _) = src.generics.split_for_impl();
+
+    // Methods bounded by `Self: Sized` can't be called on trait objects,
+    // but must be implemented unless they have default bodies.
+    let unreachables = src
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TraitItem::Fn(TraitItemFn {
+                sig, default: None, ..
+            }) if has_sized_self_bound(sig) => Some(sig),
+            _ => None,
+        })
+        .map(|sig| {
+            let message = "trait objects can't call methods bounded by `Self: Sized`";
+            let body = if sig.asyncness.is_none() && get_future_output(sig).is_some() {
+                quote! { async move { unreachable!(#message) } }
+            } else {
+                quote! { unreachable!(#message) }
+            };
+            quote! {
+                #[allow(unused_variables)]
+                #sig {
+                    #body
+                }
+            }
+        });
+
+    // Trait objects can't give values of associated constants, so the ones without default
+    // values fail to compile when they're used through trait objects.
+    // Others report their default values, and accessors like `erased_const_kind()`
+    // give the values of implementors.
+    let consts = src
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TraitItem::Const(TraitItemConst {
+                ident,
+                ty,
+                default: None,
+                ..
+            }) => Some((ident, ty)),
+            _ => None,
+        })
+        .map(|(ident, ty)| {
+            let accessor_ident = gen_const_accessor_ident(ident);
+            let message =
+                format!("trait objects can't give `{ident}`; call `{accessor_ident}()` instead");
+            quote! { const #ident: #ty = panic!(#message); }
+        });
+
     // Gets source and erased trait method signatures.
     let mut src = src.clone();
-    let mut src_temp = src.clone();
+    let mut src_temp = lifted.clone();
     let mut erased = erased.clone();
     let src_sigs = get_signatures(&mut src);
+    // Accessors of associated constants at the end are not in the generic trait.
     let erased_sigs = get_signatures(&mut erased);
+    let erased_sigs = &erased_sigs[..src_sigs.len()];
 
     // Gets erased method names.
     let erased_method_idents = erased_sigs.iter().map(|sig| &sig.ident);
 
-    // Temporary source signatures now contain &TypeId parameters for easy comparison.
+    // Temporary source signatures now contain key parameters for easy comparison.
     let mut src_temp_sigs = get_signatures(&mut src_temp);
     for sig in src_temp_sigs.iter_mut() {
         if is_generic(sig) {
-            inject_type_id(sig);
+            **sig = normalize_generics(sig);
+            let key = gen_key_arg(&sig.generics);
+            inject_key(sig, key);
         }
     }
 
     // Makes method blocks.
-    let mut preprocs = Vec::new();
-    let mut args = Vec::new();
-    let mut postprocs = Vec::new();
-    for (src_sig, erased_sig) in src_temp_sigs.iter().zip(erased_sigs.iter()) {
-        let (preproc, arg, postproc) = gen_block(src_sig, erased_sig);
-        preprocs.push(preproc);
-        args.push(arg);
-        postprocs.push(postproc);
+    let mut blocks = Vec::new();
+    for (src_sig, erased_ident) in src_temp_sigs.iter().zip(erased_method_idents) {
+        let (preproc, args, postproc) = gen_block(src_sig, &ptr_path)?;
+        let block = if src_sig.asyncness.is_some() {
+            // Async? => Awaits the boxed future.
+            quote! {
+                #preproc
+                self.#erased_ident(#args).await
+            }
+        } else if get_future_output(src_sig).is_some() {
+            // Returns `impl Future`? => Awaits the boxed future in an async block
+            // that captures the arguments.
+            quote! {
+                async move {
+                    #preproc
+                    self.#erased_ident(#args).await
+                }
+            }
+        } else if postproc.is_empty() {
+            quote! {
+                #preproc
+                self.#erased_ident(#args)
+            }
+        } else {
+            // Generic return value? => Casts it back after the call.
+            quote! {
+                #preproc
+                let __ret__ = self.#erased_ident(#args);
+                #postproc
+            }
+        };
+        blocks.push(block);
     }
 
-    quote! {
-        impl #src_trait_ident for dyn #erased_trait_ident {
+    Ok(quote! {
+        #markers
+
+        impl #impl_generics #src_trait_ident #src_args for __Dyn #where_clause {
+            #(type #assoc_idents = #assoc_param_idents;)*
+            #(#consts)*
             #(
                 #[inline]
                 #src_sigs {
-                    #preprocs
-                    self. #erased_method_idents ( #args )
-                    #postprocs
+                    #blocks
                 }
             )*
+            #(#unreachables)*
         }
-    }
+    })
 }
 
 /// Generates preproc, args, and postproc codes in dyn erased method.
 fn gen_block(
     src_sig: &Signature,
-    erased_sig: &Signature,
-) -> (TokenStream2, TokenStream2, TokenStream2) {
+    ptr_path: &Path,
+) -> Result<(TokenStream2, TokenStream2, TokenStream2)> {
     if is_generic(src_sig) {
-        gen_block_generic(src_sig, erased_sig)
+        gen_block_generic(src_sig, ptr_path)
     } else {
         gen_block_non_generic(src_sig)
     }
@@ -117,36 +589,32 @@ fn gen_block(
 /// Generates preproc, args, and postproc codes in dyn erased generic method.
 fn gen_block_generic(
     src_sig: &Signature,
-    erased_sig: &Signature,
-) -> (TokenStream2, TokenStream2, TokenStream2) {
-    // let mut preprocs = Vec::new();
+    ptr_path: &Path,
+) -> Result<(TokenStream2, TokenStream2, TokenStream2)> {
+    // Assumes that there's only one generic symbol, which is validated while erasing.
+    let (key_ident, key) = match get_erased_param(&src_sig.generics) {
+        Some(GenericParam::Type(TypeParam { ident, .. })) => {
+            ("__type_id__", gen_type_id(src_sig, ident)?)
+        }
+        // Const generics are passed by their values.
+        Some(GenericParam::Const(ConstParam { ident, .. })) => (CONST_KEY, quote! { #ident }),
+        _ => return gen_block_non_generic(src_sig),
+    };
+
+    let mut preprocs = Vec::new();
     let mut args = Vec::new();
-    // let mut postprocs = Vec::new();
-
-    // Assumes that there's only one generic symbol.
-    let generic_ident = &src_sig.generics.type_params().next().unwrap().ident;
-
-    for (src_arg, erased_arg) in src_sig.inputs.iter().zip(erased_sig.inputs.iter()).skip(1) {
-        let src_arg_ident = get_ident(src_arg);
-        let src_arg_str = src_arg_ident.to_string();
-
-        // Injected &TypeId? => Adds TypeId::of::<T>() argument.
-        if src_arg_str == "__type_id__" {
-            args.push(quote! { &std::any::TypeId::of::<#generic_ident>() });
-        }
-        // Generic?
-        else if src_arg != erased_arg {
-            let (_, ty, mutability) = parse_arg(src_arg);
-            match (is_ref(ty), mutability) {
-                // Generic mutable reference? => Adds casted argument.
-                (true, Some(_)) => args.push(quote! { #src_arg_ident as &mut dyn std::any::Any }),
-                // Generic reference? => Adds casted argument.
-                (true, None) => args.push(quote! { #src_arg_ident as &dyn std::any::Any }),
-                // Moved generic? => Looks not good to implement.
-                _ => {
-                    unimplemented!()
-                }
-            }
+    for src_arg in src_sig.inputs.iter().skip(1) {
+        let src_arg_ident = get_ident(src_arg)?;
+        let (_, ty, _) = parse_arg(src_arg)?;
+
+        // Injected key? => Adds the key like `&TypeId::of::<T>()`.
+        if src_arg_ident == key_ident {
+            args.push(key.clone());
+        }
+        // Generic? => Adds casted argument.
+        else if let Some(erased) = ErasedArg::new(ty, &src_sig.generics)? {
+            preprocs.push(erased.gen_preproc(src_arg_ident));
+            args.push(erased.gen_upcast(src_arg_ident, &src_sig.generics, ptr_path)?);
         }
         // Not a generic argument? => No manipulation.
         else {
@@ -154,12 +622,64 @@ fn gen_block_generic(
         }
     }
 
-    (quote! {}, quote! { #(#args),* }, quote! {})
+    // Generic return value? => Downcasts it.
+    let postproc = match get_erased_output(src_sig)? {
+        Some(erased) => {
+            erased.gen_downcast(&format_ident!("__ret__"), &src_sig.generics, ptr_path)?
+        }
+        None => quote! {},
+    };
+
+    Ok((quote! { #(#preprocs)* }, quote! { #(#args),* }, postproc))
+}
+
+/// Generates `&TypeId` of the generic type to find out the function to be called.
+fn gen_type_id(src_sig: &Signature, generic_ident: &Ident) -> Result<TokenStream2> {
+    if !is_impl_trait_param(generic_ident) {
+        return Ok(quote! { &std::any::TypeId::of::<#generic_ident>() });
+    }
+
+    // `impl Trait` can't be named, so gets `TypeId` from the argument.
+    let arg = src_sig
+        .inputs
+        .iter()
+        .find(|arg| mentions_any(arg, std::iter::once(generic_ident.to_string().as_str())))
+        .unwrap();
+    let (arg_ident, ty, _) = parse_arg(arg)?;
+    let erased = ErasedArg::new(ty, &src_sig.generics)?;
+    let elem = match erased {
+        Some(ErasedArg::Ref(TypeReference { elem, .. })) => Some(elem.as_ref()),
+        Some(ErasedArg::Box(elem)) => Some(elem),
+        Some(ErasedArg::Value(ty)) => Some(ty),
+        _ => None,
+    };
+    let is_direct = elem.is_some_and(|elem| {
+        matches!(elem, Type::Path(TypePath { qself: None, path }) if path.is_ident(generic_ident))
+    });
+    if !is_direct {
+        return Err(syn::Error::new_spanned(
+            ty,
+            "`impl Trait` arguments must be passed as `impl Trait`, `&impl Trait`, \
+            `&mut impl Trait` or `Box<impl Trait>` for now; use a named generic parameter instead",
+        ));
+    }
+    if matches!(erased, Some(ErasedArg::Value(..))) {
+        Ok(quote! { &std::any::Any::type_id(&#arg_ident) })
+    } else {
+        Ok(quote! { &std::any::Any::type_id(&*#arg_ident) })
+    }
 }
 
 /// Generates preproc, args, and postproc codes in dyn erased non generic method.
-fn gen_block_non_generic(src_sig: &Signature) -> (TokenStream2, TokenStream2, TokenStream2) {
-    let arg_idents = src_sig.inputs.iter().skip(1).map(get_ident);
+fn gen_block_non_generic(
+    src_sig: &Signature,
+) -> Result<(TokenStream2, TokenStream2, TokenStream2)> {
+    let arg_idents = src_sig
+        .inputs
+        .iter()
+        .skip(1)
+        .map(get_ident)
+        .collect::<Result<Vec<_>>>()?;
 
-    (quote! {}, quote! { #(#arg_idents),* }, quote! {})
+    Ok((quote! {}, quote! { #(#arg_idents),* }, quote! {}))
 }
@@ -1,278 +1,1324 @@
 use crate::common::*;
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
-use syn::parse::Parser;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream, Parser};
 use syn::{
-    parse_macro_input, parse_quote, Block, Field, Fields, FieldsNamed, Ident, ItemStruct,
-    Signature, TraitItemFn, Type,
+    parenthesized, parse_macro_input, parse_quote, Attribute, Block, ConstParam, Error, Field,
+    Fields, FieldsNamed, FieldsUnnamed, GenericArgument, GenericParam, Generics, Ident, Index,
+    Item, ItemEnum, ItemStruct, Lifetime, Member, Path, PathArguments, Result, ReturnType,
+    Signature, Token, TraitBound, TraitBoundModifier, TraitItem, TraitItemConst, TraitItemFn,
+    TraitItemType, Type, TypeImplTrait, TypeParam, TypeParamBound, TypePath, TypeReference,
+    Visibility,
 };
 
-/// Injects function table fields for the generic methods,
-/// which name is something like `fn_table_foo`, into the struct.
+/// Arguments of `inject_fn_table` attribute.
+/// Looks like `ErasedGeneric; fn foo<T: Bound>(&mut self, t: &T); ...`,
+/// or `Generic as ErasedGeneric; ...` with the generic trait.
+/// Generic traits are given with their arguments like `Storage<u32> as ErasedStorage<u32>`.
+/// Erased type parameters of the trait are given as they are like `Sink<T> as ErasedSink`,
+/// and the methods take them as their own generic parameters like `fn push<T: Bound>(..)`.
+/// Associated types and constants like `type Output;` can be listed as well.
+/// Options of the table follow the trait like `ErasedGeneric, field(pub handlers), default(A, B)`.
+struct Attr {
+    src_path: Option<Path>,
+    erased_path: Path,
+    field: FieldOptions,
+    /// Types that the table is filled with by `Default`.
+    defaults: Option<FnTableTypes>,
+    items: Vec<TraitItem>,
+}
+
+impl Parse for Attr {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let path: Path = input.parse()?;
+        let (src_path, erased_path) = if input.peek(Token![as]) {
+            input.parse::<Token![as]>()?;
+            (Some(path), input.parse()?)
+        } else {
+            (None, path)
+        };
+        let mut field = FieldOptions::default();
+        let mut defaults = None;
+        while input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            // `default` is a keyword.
+            let option = Ident::parse_any(input)?;
+            let content;
+            parenthesized!(content in input);
+            if option == "field" {
+                field = content.parse()?;
+            } else if option == "default" {
+                defaults = Some(content.parse()?);
+            } else {
+                return Err(Error::new_spanned(
+                    option,
+                    "unsupported option; expected `field(..)` or `default(..)`",
+                ));
+            }
+        }
+        let mut items = Vec::new();
+        if !input.is_empty() {
+            input.parse::<Token![;]>()?;
+        }
+        while !input.is_empty() {
+            items.push(input.parse()?);
+        }
+        Ok(Self {
+            src_path,
+            erased_path,
+            field,
+            defaults,
+            items,
+        })
+    }
+}
+
+/// Options of the injected table field written like a field as `#[serde(skip)] pub handlers`.
+/// Each of them can be omitted, and the name is only for structs having named fields.
+/// The field has the visibility of the struct unless it's given, e.g. `pub(self)` for private.
+#[derive(Default)]
+struct FieldOptions {
+    attrs: Vec<Attribute>,
+    vis: Option<Visibility>,
+    ident: Option<Ident>,
+}
+
+impl Parse for FieldOptions {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = match input.parse()? {
+            Visibility::Inherited => None,
+            vis => Some(vis),
+        };
+        let ident = if input.is_empty() {
+            None
+        } else {
+            Some(input.parse()?)
+        };
+        Ok(Self { attrs, vis, ident })
+    }
+}
+
+/// Struct holding the function table, and the implementor of the generic trait in it.
+struct Implementor {
+    vis: Visibility,
+    /// Struct holding the function table like `Handler<'a, T>`.
+    ty: Type,
+    generics: Generics,
+    /// Implementor of the generic trait. It's the wrapped enum for enums, or the struct itself.
+    target_ty: Type,
+    /// Function table builder like `HandlerErasedGenericFnTable`.
+    builder: Ident,
+    /// Field of the function table. It's the last field of tuple structs.
+    table: Member,
+    /// Field of the wrapped enum.
+    inner: Option<Member>,
+}
+
+impl Implementor {
+    /// Generates the receiver given to the generic trait method like `__self__`,
+    /// or `&mut __self__.inner` for the wrapped enum.
+    fn gen_receiver(&self, sig: &Signature, self_ident: TokenStream2) -> Result<TokenStream2> {
+        let Some(inner) = self.inner.as_ref() else {
+            return Ok(self_ident);
+        };
+        match sig.receiver().map(|receiver| receiver.ty.as_ref()) {
+            Some(Type::Reference(TypeReference { mutability, .. })) => {
+                Ok(quote! { &#mutability #self_ident.#inner })
+            }
+            _ => Err(Error::new_spanned(
+                &sig.inputs,
+                "enums are wrapped in a struct holding the function table, \
+                so only `&self` and `&mut self` receivers are supported for them",
+            )),
+        }
+    }
+}
+
+/// Injects a function table for the generic methods into the struct,
+/// which is named after the erased trait like `fn_table_erased_generic`.
 /// Also implements erased generic for the struct.
+/// Tuple structs get the table as the last field, and unit structs become tuple structs.
+/// Enums are wrapped in a struct holding the table like `StateWithFnTable`.
 pub fn inject_fn_table(attr: TokenStream, item: TokenStream) -> TokenStream {
-    // Makes each `TokenStream` corrensponding to generic method signatures.
-    let attr = attr.to_string();
-    let erased_name = attr
-        .trim()
-        .split(';')
-        .next()
-        .expect("Must put in the name of erased generic trait.");
-    let attr_tokens = attr
-        .split(';')
-        .skip(1)
-        .filter(|line| !line.is_empty())
-        .map(|line| {
-            let mut line = line.to_owned();
-            line.push(';');
-            line.parse::<TokenStream>().unwrap()
-        })
-        .collect::<Vec<_>>();
+    let attr = parse_macro_input!(attr as Attr);
+    let item = parse_macro_input!(item as Item);
+
+    expand(attr, item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Expands `inject_fn_table` macro.
+fn expand(attr: Attr, item: Item) -> Result<TokenStream2> {
+    // Enums can't have more fields, so they're wrapped in a struct holding the table.
+    let (mut st, en) = match item {
+        Item::Struct(st) => (st, None),
+        Item::Enum(en) => (gen_wrapper(&en), Some(en)),
+        item => {
+            return Err(Error::new_spanned(
+                item,
+                "expected a struct or an enum to implement the erased trait",
+            ))
+        }
+    };
+
+    // Generic parameters of the struct are carried onto the generated items,
+    // so the struct is written like `Handler<'a, T>` in them.
+    let st_generics = st.generics.clone();
+    let st_ident = &st.ident;
+    let (_, st_args, _) = st_generics.split_for_impl();
+    let st_ty: Type = parse_quote! { #st_ident #st_args };
+    let (target_ident, target_ty, inner) = match en.as_ref() {
+        Some(en) => {
+            let en_ident = &en.ident;
+            (
+                en_ident.clone(),
+                parse_quote! { #en_ident #st_args },
+                Some(parse_quote! { inner }),
+            )
+        }
+        None => (st_ident.clone(), st_ty.clone(), None),
+    };
+
+    // Inserts new field like `fn_table_erased_generic` into the struct.
+    // Names come from the erased trait, so that a struct can implement several erased traits.
+    // Unit structs become tuple structs having only the table.
+    let erased_ident = match attr.erased_path.segments.last() {
+        Some(seg) => &seg.ident,
+        None => {
+            return Err(Error::new_spanned(
+                &attr.erased_path,
+                "expected the erased trait",
+            ))
+        }
+    };
+    let builder_ident = gen_builder_ident(&target_ident, erased_ident);
+    let FieldOptions {
+        attrs: field_attrs,
+        vis: field_vis,
+        ident: field_ident,
+    } = &attr.field;
+    let field_vis = field_vis.as_ref().unwrap_or(&st.vis);
+    let table = match &mut st.fields {
+        Fields::Named(FieldsNamed { named, .. }) => {
+            let field_ident = field_ident
+                .clone()
+                .unwrap_or_else(|| gen_table_field_ident(erased_ident));
+            named.push(Field::parse_named.parse2(quote! {
+                #(#field_attrs)* #field_vis #field_ident: #builder_ident #st_args
+            })?);
+            Member::Named(field_ident)
+        }
+        _ if field_ident.is_some() => {
+            return Err(Error::new_spanned(
+                field_ident,
+                "the table of tuple structs and unit structs can't be named",
+            ))
+        }
+        Fields::Unnamed(FieldsUnnamed { unnamed, .. }) => {
+            unnamed.push(Field::parse_unnamed.parse2(quote! {
+                #(#field_attrs)* #field_vis #builder_ident #st_args
+            })?);
+            Member::Unnamed(Index::from(unnamed.len() - 1))
+        }
+        Fields::Unit => {
+            st.fields = Fields::Unnamed(parse_quote! {
+                (#(#field_attrs)* #field_vis #builder_ident #st_args)
+            });
+            Member::Unnamed(Index::from(0))
+        }
+    };
+    let imp = Implementor {
+        vis: st.vis.clone(),
+        ty: st_ty,
+        generics: st_generics,
+        target_ty,
+        builder: builder_ident,
+        table,
+        inner,
+    };
 
     // Generates function table field for each generic method.
-    let mut st = parse_macro_input!(item as ItemStruct);
     let mut sigs = Vec::new();
     let mut builder_field_idents = Vec::new();
-    let mut table_type_idents = Vec::new();
-    let mut table_type_defines = Vec::new();
-    for t in attr_tokens {
-        let ast = parse_macro_input!(t as TraitItemFn);
+    let mut table_types = Vec::new();
+    let mut check_sigs = Vec::new();
+    let mut renames = Vec::new();
+    let mut assoc_items = Vec::new();
+    for item in attr.items {
+        let mut ast = match item {
+            TraitItem::Fn(ast) => ast,
+            item => {
+                assoc_items.push(gen_assoc_item(
+                    attr.src_path.as_ref(),
+                    &imp.target_ty,
+                    item,
+                )?);
+                continue;
+            }
+        };
+        check_sigs.push(normalize_generics(&ast.sig));
+        // Not in the erased trait? => Only checks the signature.
+        let options = ErasedOptions::take(&mut ast.attrs)?;
+        if options.skip || is_sized_only(&ast.sig) {
+            continue;
+        }
+        validate_signature(&ast.sig)?;
         sigs.push(ast.sig.clone());
-        if let Some((field_ident, table_type_ident, table_type_define)) = gen_field(ast, &st.ident)
-        {
+        renames.push(options.rename);
+        if let Some((field_ident, table_type)) = gen_field(ast, &imp, &attr.erased_path)? {
             builder_field_idents.push(field_ident);
-            table_type_idents.push(table_type_ident);
-            table_type_defines.push(table_type_define);
+            table_types.push(table_type);
         }
     }
 
-    // Implements erased generic for the struct.
-    let erased_for_st = impl_erased_for_st(erased_name, &st.ident, &sigs);
+    // Makes `impl Trait` arguments and where clauses into generic parameters.
+    let norm_sigs = sigs.iter().map(normalize_generics).collect::<Vec<_>>();
 
-    // Inserts new `fn_table` field into the struct.
-    let st_fields = match &mut st.fields {
-        Fields::Named(FieldsNamed { named, .. }) => named,
-        _ => unimplemented!(),
+    // Erased methods of non-generic structs are checked against the erased trait by constants
+    // of the builder, which the erased implementation and the other checks depend on.
+    // Generic structs can't be checked by constants.
+    let gated = if imp.generics.params.is_empty() {
+        sigs.iter().map(|sig| sig.ident.clone()).collect()
+    } else {
+        Vec::new()
     };
-    let builder_ident = clone_ident_with_suffix(&st.ident, "FnTable");
-    let fn_table_field = Field::parse_named
-        .parse2(quote! {
-            fn_table: #builder_ident
-        })
-        .unwrap();
-    st_fields.push(fn_table_field);
+
+    // Checks that the signatures are same with the generic trait.
+    let sig_checks = gen_signature_checks(
+        attr.src_path.as_ref(),
+        &attr.erased_path,
+        &imp,
+        &check_sigs,
+        &gated,
+    );
+
+    // Implements erased generic for the struct.
+    let erased_for_st = impl_erased_for_st(
+        attr.src_path.as_ref(),
+        &attr.erased_path,
+        &imp,
+        &sigs,
+        &renames,
+        &assoc_items,
+        &gated,
+    )?;
 
     // Defines and implements `fn_table` builder.
     let fn_table_builder = impl_fn_table_builder(
-        &st,
-        &builder_ident,
+        &imp,
+        attr.src_path.as_ref(),
+        &attr.erased_path,
         &builder_field_idents,
-        &table_type_idents,
-        &sigs,
-    );
+        &table_types,
+        &norm_sigs,
+        attr.defaults.as_ref(),
+    )?;
 
-    quote! {
+    // Enums come with the wrapper, which derefs to the enum.
+    let wrapper = en.map(|en| impl_wrapper(en, &imp));
+
+    Ok(quote! {
+        #wrapper
         #st
-        #(#table_type_defines)*
+        #sig_checks
         #erased_for_st
         #fn_table_builder
+    })
+}
+
+/// Makes a struct wrapping the enum, which holds the function table instead of the enum,
+/// like `struct StateWithFnTable { inner: State }`.
+fn gen_wrapper(en: &ItemEnum) -> ItemStruct {
+    let vis = &en.vis;
+    let en_ident = &en.ident;
+    let ident = clone_ident_with_suffix(en_ident, "WithFnTable");
+    let generics = &en.generics;
+    let (_, ty_generics, where_clause) = en.generics.split_for_impl();
+    parse_quote! {
+        #vis struct #ident #generics #where_clause {
+            #vis inner: #en_ident #ty_generics,
+        }
     }
-    .into()
 }
 
-/// Generates function table field.
-fn gen_field(ast: TraitItemFn, st_ident: &Ident) -> Option<(Ident, Ident, TokenStream2)> {
-    // Nothing for non-generic method.
-    is_generic(&ast.sig).then_some(0)?;
+/// Defines the enum, and implements `Deref` and `DerefMut` to the enum for the wrapper.
+fn impl_wrapper(en: ItemEnum, imp: &Implementor) -> TokenStream2 {
+    let st_ty = &imp.ty;
+    let target_ty = &imp.target_ty;
+    let (impl_generics, _, where_clause) = imp.generics.split_for_impl();
+    quote! {
+        #en
 
-    // Gathers input and output types.
-    let mut input_types: Vec<Type> = Vec::new();
-    for arg in ast.sig.inputs {
-        let (ident, _, mutability) = parse_arg(&arg);
-        match (ident.to_string().as_str(), mutability) {
-            ("Self", None) => input_types.push(parse_quote! { &#st_ident }),
-            ("Self", Some(..)) => input_types.push(parse_quote! { &mut #st_ident }),
-            _ => {
-                let symbols = get_generic_symbols(&ast.sig.generics);
-                let mut arg = arg.clone();
-                change_arg_to_any(&mut arg, symbols.iter().map(|s| s.as_str()));
-                let (_, ty, _) = parse_arg(&arg);
-                input_types.push(ty.clone());
+        impl #impl_generics std::ops::Deref for #st_ty #where_clause {
+            type Target = #target_ty;
+
+            fn deref(&self) -> &Self::Target {
+                &self.inner
             }
         }
+
+        impl #impl_generics std::ops::DerefMut for #st_ty #where_clause {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.inner
+            }
+        }
+    }
+}
+
+/// Generates assertions that the given signatures are same with the ones in the generic trait.
+/// Each method of the struct is coerced into a function pointer,
+/// then it must implement a trait only implemented for the function pointer of the signature.
+/// So that users can see which signature is wrong instead of errors in generated code.
+/// Methods checked against the erased trait by `gate_erased_signature` are only checked here
+/// if that check passes, so that the same mistake isn't reported twice.
+fn gen_signature_checks(
+    src_path: Option<&Path>,
+    erased_path: &Path,
+    imp: &Implementor,
+    sigs: &[Signature],
+    gated: &[Ident],
+) -> TokenStream2 {
+    let st_ty = &imp.target_ty;
+    let st_generics = &imp.generics;
+    let checks = sigs.iter().map(|sig| {
+        let method_ident = &sig.ident;
+        let (lifetimes, inputs, mut output) = get_fn_pointer_types(sig, st_ty, erased_path);
+        // Generic parameters of the struct are needed as well.
+        let generics = merge_generics(st_generics, &sig.generics);
+        let mut params = generics.params.clone();
+
+        // Futures can't be named, so they are bounded by their outputs.
+        if let Type::ImplTrait(TypeImplTrait { bounds, .. }) = &output {
+            params.push(parse_quote! { __Fut: #bounds });
+            output = parse_quote! { __Fut };
+        } else if sig.asyncness.is_some() {
+            params.push(parse_quote! { __Fut: std::future::Future<Output = #output> });
+            output = parse_quote! { __Fut };
+        }
+        let where_clause = &generics.where_clause;
+        let (type_params, decl_params): (Vec<_>, Vec<_>) = sig
+            .generics
+            .params
+            .iter()
+            .filter_map(|param| match param {
+                GenericParam::Type(TypeParam { ident, .. }) => {
+                    Some((ident, quote! { #ident: ?Sized }))
+                }
+                GenericParam::Const(ConstParam { ident, ty, .. }) => {
+                    Some((ident, quote! { const #ident: #ty }))
+                }
+                GenericParam::Lifetime(..) => None,
+            })
+            .unzip();
+        let placeholders = inputs.iter().map(|_| quote! { _ });
+
+        // Makes the method path and error message.
+        let method_path = if let Some(src_path) = src_path {
+            quote! { <#st_ty as #src_path>::#method_ident }
+        } else {
+            quote! { <#st_ty>::#method_ident }
+        };
+        let message = gen_mismatch_message(src_path, method_ident);
+        // Type parameters of the trait are given to the trait, not to the method.
+        let mut method_generics = sig.generics.clone();
+        let trait_params = get_trait_params(src_path, &sig.generics);
+        method_generics.params = method_generics
+            .params
+            .into_iter()
+            .filter(|param| {
+                !matches!(param, GenericParam::Type(TypeParam { ident, .. })
+                    if trait_params.contains(&ident))
+            })
+            .collect();
+        let turbofish = gen_turbofish(&method_generics);
+
+        // Errors are reported at the method name.
+        let span = method_ident.span();
+        let ret = if gated.contains(method_ident) {
+            let builder = &imp.builder;
+            let check_ident = gen_check_ident(method_ident);
+            quote! { <[_; #builder::#check_ident] as std::iter::IntoIterator>::Item }
+        } else {
+            quote! { _ }
+        };
+        let coerce = quote_spanned! { span=>
+            let f: fn(#(#placeholders),*) -> #ret = #method_path #turbofish;
+        };
+        let assert = quote_spanned! { span=>
+            assert::<#(#type_params,)* _>(f);
+        };
+
+        quote! {
+            #[allow(dead_code)]
+            const _: () = {
+                #[diagnostic::on_unimplemented(
+                    message = #message,
+                    label = "signature written in `inject_fn_table`",
+                    note = "signatures in `inject_fn_table` must be same with the ones in the trait"
+                )]
+                trait Signature<#(#decl_params),*> {}
+
+                impl<#(#lifetimes,)* #params> Signature<#(#type_params),*>
+                for fn(#(#inputs),*) -> #output #where_clause {}
+
+                fn assert<#(#decl_params,)* __F: Signature<#(#type_params),*>>(_: __F) {}
+
+                fn check<#params>(_: std::marker::PhantomData<#st_ty>) #where_clause {
+                    #coerce
+                    #assert
+                }
+            };
+        }
+    });
+
+    quote! { #(#checks)* }
+}
+
+/// Makes the error message of the signature checks.
+fn gen_mismatch_message(src_path: Option<&Path>, method_ident: &Ident) -> String {
+    if let Some(src_path) = src_path {
+        let src_name = src_path.to_token_stream().to_string().replace(' ', "");
+        format!("signature of `{method_ident}` does not match `{src_name}::{method_ident}`")
+    } else {
+        format!("signature of `{method_ident}` does not match the generic trait")
+    }
+}
+
+/// Makes the return type of the erased method depend on a check of the method against
+/// the erased trait, like `<[usize; HandlerFnTable::__CHECK_SIZE] as IntoIterator>::Item`.
+/// The check is a constant of the builder, which is returned.
+/// If the check fails, the return type is unknown, so rustc reports the check
+/// instead of the mismatch between the generated method and the erased trait.
+/// The check is in a closure, which is type checked with the constant but never evaluated.
+fn gate_erased_signature(
+    src_path: Option<&Path>,
+    erased_path: &Path,
+    imp: &Implementor,
+    sig: &Signature,
+    esig: &mut Signature,
+) -> TokenStream2 {
+    let st_ty = &imp.ty;
+    let method_ident = &sig.ident;
+    let esig_ident = &esig.ident;
+    let (elided, inputs, output) = get_fn_pointer_types(esig, st_ty, erased_path);
+    let lifetimes = esig
+        .generics
+        .lifetimes()
+        .map(|param| &param.lifetime)
+        .chain(elided.iter());
+    let placeholders = inputs.iter().map(|_| quote! { _ });
+    let message = gen_mismatch_message(src_path, method_ident);
+
+    // Errors are reported at the method name.
+    let span = method_ident.span();
+    let coerce = quote_spanned! { span=>
+        let f: fn(#(#placeholders),*) -> _ = <#st_ty as #erased_path>::#esig_ident;
+    };
+    let assert = quote_spanned! { span=>
+        assert(f);
+    };
+    let check_ident = gen_check_ident(method_ident);
+    let check = quote! {
+        const #check_ident: usize = {
+            #[diagnostic::on_unimplemented(
+                message = #message,
+                label = "signature written in `inject_fn_table`",
+                note = "signatures in `inject_fn_table` must be same with the ones in the trait"
+            )]
+            trait Signature {}
+
+            impl<#(#lifetimes),*> Signature for fn(#(#inputs),*) -> #output {}
+
+            fn assert<__F: Signature>(_: __F) {}
+
+            let _ = || {
+                #coerce
+                #assert
+            };
+            1
+        };
+    };
+
+    let builder = &imp.builder;
+    let ret = match &esig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) => quote! { #ty },
+    };
+    esig.output = parse_quote! {
+        -> <[#ret; #builder::#check_ident] as std::iter::IntoIterator>::Item
+    };
+    check
+}
+
+/// Names the constant checking the method like `__CHECK_SIZE` for `size`.
+fn gen_check_ident(method_ident: &Ident) -> Ident {
+    format_ident!(
+        "__CHECK_{}",
+        method_ident.unraw().to_string().to_uppercase()
+    )
+}
+
+/// Gets type parameters of the method which are given to the generic trait,
+/// like `T` in `fn push<T: Bound>(&mut self, t: &T)` of `Sink<T> as ErasedSink`.
+fn get_trait_params<'a>(src_path: Option<&Path>, generics: &'a Generics) -> Vec<&'a Ident> {
+    let Some(PathArguments::AngleBracketed(args)) = src_path
+        .and_then(|path| path.segments.last())
+        .map(|seg| &seg.arguments)
+    else {
+        return Vec::new();
+    };
+    generics
+        .type_params()
+        .map(|param| &param.ident)
+        .filter(|ident| {
+            args.args.iter().any(|arg| {
+                matches!(arg, GenericArgument::Type(Type::Path(TypePath { qself: None, path }))
+                    if path.is_ident(*ident))
+            })
+        })
+        .collect()
+}
+
+/// Generates an associated type or an accessor method of an associated constant
+/// for the erased trait implementation.
+/// They come from the generic trait implementation, so that the generic trait must be given.
+fn gen_assoc_item(src_path: Option<&Path>, st_ty: &Type, item: TraitItem) -> Result<TokenStream2> {
+    let src_path = || {
+        src_path.ok_or_else(|| {
+            Error::new_spanned(
+                &item,
+                "associated items need the generic trait; \
+                write it like `Generic as ErasedGeneric`",
+            )
+        })
+    };
+    match &item {
+        TraitItem::Type(TraitItemType {
+            ident,
+            default: Some((_, ty)),
+            ..
+        }) => Ok(quote! { type #ident = #ty; }),
+        TraitItem::Type(TraitItemType { ident, .. }) => {
+            let src_path = src_path()?;
+            Ok(quote! { type #ident = <#st_ty as #src_path>::#ident; })
+        }
+        TraitItem::Const(TraitItemConst { ident, ty, .. }) => {
+            let src_path = src_path()?;
+            let accessor_ident = gen_const_accessor_ident(ident);
+            Ok(quote! {
+                #[inline]
+                fn #accessor_ident(&self) -> #ty {
+                    <#st_ty as #src_path>::#ident
+                }
+            })
+        }
+        _ => Err(Error::new_spanned(
+            &item,
+            "only methods, associated types and constants can be listed",
+        )),
+    }
+}
+
+/// Generates function table field.
+/// The table type is written in the builder rather than as a type alias,
+/// so the builder is the only item named after the struct and the erased trait.
+fn gen_field(
+    ast: TraitItemFn,
+    imp: &Implementor,
+    erased_path: &Path,
+) -> Result<Option<(Ident, Type)>> {
+    // Nothing for non-generic method.
+    if !is_generic(&ast.sig) {
+        return Ok(None);
     }
-    let output_type = ast.sig.output;
-
-    // Makes a type alias for a function table field to be injected.
-    let table_name = format!("fn_table_{}", ast.sig.ident);
-    let table_name = camel_case(&table_name);
-    let table_type_ident = gen_ident(&table_name);
-    let table_type_define = quote! {
-        type #table_type_ident = std::collections::HashMap<
-            std::any::TypeId,
-            std::boxed::Box<
-                dyn std::ops::Fn(
-                    #(#input_types),*
-                ) #output_type
-            >,
+    let sig = normalize_generics(&ast.sig);
+
+    // Gathers input and output types.
+    let (lifetimes, input_types, output_type) = get_entry_types(&sig, &imp.ty, erased_path)?;
+    let for_lifetimes = if lifetimes.is_empty() {
+        quote! {}
+    } else {
+        quote! { for<#(#lifetimes),*> }
+    };
+
+    // Makes the type of a function table field to be injected.
+    let key_type = get_key_type(&sig);
+    let table_type = parse_quote! {
+        std::collections::HashMap<
+            #key_type,
+            #for_lifetimes fn(#(#input_types),*) -> #output_type,
             ahash::RandomState
-        >;
+        >
     };
 
     // TODO: Combinations of TypeId for multiple generics.
     let field_ident = ast.sig.ident.clone();
 
-    Some((field_ident, table_type_ident, table_type_define))
+    Ok(Some((field_ident, table_type)))
 }
 
-/// Implements erased generic for the struct.
-fn impl_erased_for_st(erased_name: &str, st_ident: &Ident, sigs: &[Signature]) -> TokenStream2 {
-    let erased_ident = clone_ident_with_name(st_ident, erased_name.trim());
+/// Gets key type of the table for the generic method.
+/// It's `TypeId` for generic types, and the type of the value for const generics.
+fn get_key_type(sig: &Signature) -> Type {
+    match get_erased_param(&sig.generics) {
+        Some(GenericParam::Const(ConstParam { ty, .. })) => ty.clone(),
+        _ => parse_quote! { std::any::TypeId },
+    }
+}
+
+/// Gets input and output types of the table entry for the generic method.
+/// Lifetimes including elided ones are returned together
+/// so that the entry can be higher-ranked over them.
+fn get_entry_types(
+    sig: &Signature,
+    st_ty: &Type,
+    erased_path: &Path,
+) -> Result<(Vec<Lifetime>, Vec<Type>, Type)> {
+    let mut sig = sig.clone();
+    let generics = sig.generics.clone();
+    change_args_to_anys(sig.inputs.iter_mut(), &generics)?;
+    change_output_to_any(&mut sig)?;
+    into_boxed_future(&mut sig);
+    let (elided, inputs, output) = get_fn_pointer_types(&sig, st_ty, erased_path);
+    let lifetimes = sig
+        .generics
+        .lifetimes()
+        .map(|param| param.lifetime.clone())
+        .chain(elided)
+        .collect();
+    Ok((lifetimes, inputs, output))
+}
 
+/// Implements erased generic for the struct.
+fn impl_erased_for_st(
+    src_path: Option<&Path>,
+    erased_path: &Path,
+    imp: &Implementor,
+    sigs: &[Signature],
+    renames: &[Option<Ident>],
+    assoc_items: &[TokenStream2],
+    gated: &[Ident],
+) -> Result<TokenStream2> {
     let mut erased_sigs = sigs.to_owned();
     let mut is_generics = Vec::new();
-    for sig in erased_sigs.iter_mut() {
+    for (sig, rename) in erased_sigs.iter_mut().zip(renames) {
         is_generics.push(is_generic(sig));
-        modify_signature_to_erased(sig);
+        modify_signature_to_erased(sig)?;
+        if let Some(rename) = rename {
+            sig.ident = rename.clone();
+        }
+    }
+
+    let mut checks = Vec::new();
+    for (sig, esig) in sigs.iter().zip(erased_sigs.iter_mut()) {
+        if gated.contains(&sig.ident) {
+            checks.push(gate_erased_signature(src_path, erased_path, imp, sig, esig));
+        }
     }
 
     let mut blocks = Vec::new();
     for (i, &is_generic) in is_generics.iter().enumerate() {
-        let sig = sigs.get(i).unwrap();
-        let esig = erased_sigs.get(i).unwrap();
-        let arg_idents = get_idents(&esig.inputs);
+        let sig = &sigs[i];
+        let esig = &erased_sigs[i];
+        let arg_idents = get_idents(&esig.inputs)?;
 
         // TODO: Combinations of TypeId for multiple generics.
         let sig_ident = &sig.ident;
+        let table = &imp.table;
         let block: Block = if is_generic {
-            // Skips self and __type_id__.
+            // Skips self and the key.
+            let key = match get_erased_param(&sig.generics) {
+                Some(GenericParam::Const(..)) => {
+                    let key_ident = &arg_idents[1];
+                    quote! { &#key_ident }
+                }
+                _ => quote! { __type_id__ },
+            };
             let arg_idents = arg_idents.iter().skip(2);
             parse_quote! {{
-                let fn_table = self
-                    .fn_table
+                let delegator = *self
+                    .#table
                     .#sig_ident
-                    .take()
-                    .expect("fn_table must be filled.");
-                let delegator = fn_table
-                    .get(__type_id__)
+                    .as_ref()
+                    .expect("fn_table must be filled.")
+                    .get(#key)
                     .expect("fn_table doesn't have appropriate entry.");
-                let ret = (delegator)(self, #(#arg_idents),*);
-                self.fn_table.#sig_ident = Some(fn_table);
-                ret
+                (delegator)(self, #(#arg_idents),*)
             }}
         } else {
             // Skips self.
-            let arg_idents = arg_idents.iter().skip(1);
-            parse_quote! {{
-                self.#sig_ident(#(#arg_idents),*)
-            }}
+            let arg_idents = arg_idents.iter().skip(1).map(|ident| quote! { #ident });
+            let arg_idents = arg_idents.collect::<Vec<_>>();
+            let call = gen_call(imp, src_path, sig, quote! { self }, quote! {}, &arg_idents)?;
+            // Async? => Boxes the concrete future.
+            if get_future_output(sig).is_some() {
+                parse_quote! {{ std::boxed::Box::pin(#call) }}
+            } else {
+                parse_quote! {{ #call }}
+            }
         };
         blocks.push(block);
     }
 
-    quote! {
-        impl #erased_ident for #st_ident {
+    let st_ty = &imp.ty;
+    let builder = &imp.builder;
+    let checks = (!checks.is_empty()).then(|| {
+        quote! {
+            impl #builder {
+                #(#checks)*
+            }
+        }
+    });
+    let (impl_generics, _, where_clause) = imp.generics.split_for_impl();
+    Ok(quote! {
+        #checks
+
+        impl #impl_generics #erased_path for #st_ty #where_clause {
+            #(#assoc_items)*
             #(
                 #[inline]
                 #erased_sigs
                 #blocks
             )*
         }
-    }
+    })
 }
 
 /// Implements a function table builder for the struct.
+/// The builder takes the generic parameters of the struct like `HandlerFnTable<'a, T>`.
 fn impl_fn_table_builder(
-    st: &ItemStruct,
-    ident: &Ident,
+    imp: &Implementor,
+    src_path: Option<&Path>,
+    erased_path: &Path,
     field_idents: &[Ident],
-    field_type_idents: &[Ident],
+    field_types: &[Type],
     sigs: &[Signature],
-) -> TokenStream2 {
+    defaults: Option<&FnTableTypes>,
+) -> Result<TokenStream2> {
+    let ident = &imp.builder;
+    let st_ty = &imp.ty;
+    let (impl_generics, ty_generics, where_clause) = imp.generics.split_for_impl();
+
+    // Generic parameters of the struct may not be used by the tables,
+    // so they are marked as used. The marker also brings implied bounds of the struct
+    // like `T: 'a` into the builder.
+    let (marker_field, marker_value) = if imp.generics.params.is_empty() {
+        (None, None)
+    } else {
+        (
+            Some(quote! { _marker: std::marker::PhantomData<#st_ty>, }),
+            Some(quote! { _marker: std::marker::PhantomData, }),
+        )
+    };
+
     // Defines a function table builder.
-    let vis = &st.vis;
+    let vis = &imp.vis;
+    let generics = &imp.generics;
     let builder = quote! {
-        #vis struct #ident {
+        #vis struct #ident #generics #where_clause {
+            #marker_field
             #(
-                #field_idents: std::option::Option<#field_type_idents>
-            ),*
+                #field_idents: std::option::Option<#field_types>,
+            )*
         }
     };
 
-    // TODO: Currently, assumes that there must be only one kind of trait bound.
-    // So, what's the first generic param?
-    let common_generic = sigs
-        .iter()
-        .filter_map(|sig| get_nth_generic(sig, 0))
-        .next()
-        .expect("Expects only one generic parameter for now.");
-    let common_generic_ident = gen_ident(&get_generic_symbol(common_generic));
+    // Makes empty tables.
+    let new = quote! {
+        #vis fn new() -> Self {
+            Self {
+                #marker_value
+                #(
+                    #field_idents: std::option::Option::Some(
+                        std::default::Default::default()
+                    ),
+                )*
+            }
+        }
+    };
 
-    // Makes an iterator generating code of inserting entries into the table.
-    let st_ident = &st.ident;
-    let insert_blocks = sigs.iter().filter(|sig| is_generic(sig)).map(|sig| {
-        let ident = &sig.ident;
-
-        // Assumes that the first arg is &mut self.
-        let symbols = get_generic_symbols(&sig.generics);
-        let mut args = sig.inputs.clone();
-        change_args_to_anys(args.iter_mut(), symbols.iter().map(|s| s.as_str()));
-        let args = args.iter().skip(1);
-
-        // Casts arguments with &dyn Any or &dyn mut Any types.
-        let casted = args.clone().map(|arg| {
-            let (ident, _, mutability) = parse_arg(arg);
-            if is_any(arg) {
-                if mutability.is_some() {
-                    quote! { #ident.downcast_mut::<#common_generic_ident>().unwrap() }
-                } else {
-                    quote! { #ident.downcast_ref::<#common_generic_ident>().unwrap() }
-                }
-            } else {
-                quote! { #ident }
+    // Generic methods are grouped by their trait bounds.
+    // Each group has its own `with` and `add` methods, which are named after the bounds
+    // like `with_component` and `add_component`.
+    // Plain `with` and `add` are aliases of them if there's only one group.
+    let groups = group_by_bounds(sigs)?;
+    let mut group_methods = Vec::new();
+    for group in groups.iter() {
+        let with_ident = gen_ident(&format!("with_{}", group.name));
+        let add_ident = gen_ident(&format!("add_{}", group.name));
+        let group_generic = &group.generic;
+        let group_generic_ident = match group_generic {
+            GenericParam::Const(ConstParam { ident, .. }) => ident,
+            GenericParam::Type(TypeParam { ident, .. }) => ident,
+            GenericParam::Lifetime(..) => unreachable!(),
+        };
+        let insert_blocks = group
+            .sigs
+            .iter()
+            .map(|sig| gen_insert_block(imp, src_path, erased_path, sig, group_generic_ident))
+            .collect::<Result<Vec<_>>>()?;
+
+        group_methods.push(quote! {
+            #[allow(dead_code)]
+            #vis fn #with_ident <#group_generic> (mut self) -> Self {
+                self.#add_ident::<#group_generic_ident>();
+                self
             }
-        });
 
-        quote! {
-            if let Some(map) = self.#ident.as_mut() {
-                map.insert(
-                    std::any::TypeId::of::<#common_generic_ident>(),
-                    std::boxed::Box::new(|s: &mut #st_ident, #(#args),*| {
-                        s.#ident::<#common_generic_ident>(#(#casted),*)
-                    })
-                );
+            #[allow(dead_code)]
+            #vis fn #add_ident <#group_generic> (&mut self) -> &mut Self {
+                #(#insert_blocks)*
+                self
             }
+        });
+
+        if groups.len() == 1 {
+            group_methods.push(quote! {
+                #[allow(dead_code)]
+                #vis fn with <#group_generic> (self) -> Self {
+                    self.#with_ident::<#group_generic_ident>()
+                }
+
+                #[allow(dead_code)]
+                #vis fn add <#group_generic> (&mut self) -> &mut Self {
+                    self.#add_ident::<#group_generic_ident>()
+                }
+            });
         }
-    });
+    }
 
     // Implements the builder.
     let impl_builder = quote! {
-        impl #ident {
-            fn new() -> Self {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #new
+
+            #(#group_methods)*
+        }
+    };
+
+    // Implements common traits for the builder, so that derives on the struct keep working.
+    // `Default` fills the tables with the types given by `default(..)` option.
+    let default_chain = defaults.map(|defaults| defaults.gen_chain("with"));
+    let impl_traits = quote! {
+        impl #impl_generics std::default::Default for #ident #ty_generics #where_clause {
+            fn default() -> Self {
+                Self::new() #default_chain
+            }
+        }
+
+        impl #impl_generics std::clone::Clone for #ident #ty_generics #where_clause {
+            fn clone(&self) -> Self {
                 Self {
+                    #marker_value
+                    #(
+                        #field_idents: std::clone::Clone::clone(&self.#field_idents),
+                    )*
+                }
+            }
+        }
+
+        // Shows keys of the tables.
+        impl #impl_generics std::fmt::Debug for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct(stringify!(#ident))
                     #(
-                        #field_idents: std::option::Option::Some(
-                            #field_type_idents::default()
+                        .field(
+                            stringify!(#field_idents),
+                            &self.#field_idents.as_ref().map(|table| {
+                                table.keys().collect::<std::vec::Vec<_>>()
+                            }),
                         )
-                    ),*
+                    )*
+                    .finish()
+            }
+        }
+    };
+
+    Ok(quote! {
+        #builder
+        #impl_builder
+        #impl_traits
+    })
+}
+
+/// Generic methods having the same trait bounds, or const generic methods having the same type.
+struct BoundGroup<'a> {
+    /// Full trait bounds or const type, which the methods are grouped by.
+    key: String,
+    /// Name of the group, which is made from the trait bounds or the const type.
+    name: String,
+    /// Generic parameter of the builder, which has all bounds of the methods in the group.
+    generic: GenericParam,
+    sigs: Vec<&'a Signature>,
+}
+
+/// Groups generic methods by trait bounds of their generic parameters.
+fn group_by_bounds(sigs: &[Signature]) -> Result<Vec<BoundGroup<'_>>> {
+    let mut groups: Vec<BoundGroup> = Vec::new();
+    for sig in sigs.iter().filter(|sig| is_generic(sig)) {
+        let param = match get_erased_param(&sig.generics) {
+            Some(GenericParam::Type(param)) => param,
+            // Const generics are grouped by their types.
+            Some(GenericParam::Const(param)) => {
+                let key = gen_const_group_name(&param.ty, path_tokens)?;
+                if let Some(group) = groups.iter_mut().find(|group| group.key == key) {
+                    group.sigs.push(sig);
+                } else {
+                    let mut generic = param.clone();
+                    generic.eq_token = None;
+                    generic.default = None;
+                    groups.push(BoundGroup {
+                        key,
+                        name: gen_const_group_name(&param.ty, last_segment_name)?,
+                        generic: GenericParam::Const(generic),
+                        sigs: vec![sig],
+                    });
                 }
+                continue;
             }
+            _ => {
+                return Err(Error::new_spanned(
+                    &sig.generics,
+                    "expected a generic type or const parameter",
+                ))
+            }
+        };
 
-            fn with <#common_generic> (mut self) -> Self {
-                self.add::<#common_generic_ident>();
-                self
+        // Same traits, same group. Other bounds are merged into the group.
+        let key = gen_group_name(param, path_tokens);
+        if let Some(group) = groups.iter_mut().find(|group| group.key == key) {
+            if let GenericParam::Type(generic) = &mut group.generic {
+                for bound in param.bounds.iter() {
+                    let key = bound.to_token_stream().to_string();
+                    if generic
+                        .bounds
+                        .iter()
+                        .all(|b| b.to_token_stream().to_string() != key)
+                    {
+                        generic.bounds.push(bound.clone());
+                    }
+                }
             }
+            group.sigs.push(sig);
+        } else {
+            let mut generic = param.clone();
+            generic.eq_token = None;
+            generic.default = None;
+            groups.push(BoundGroup {
+                key,
+                name: gen_group_name(param, last_segment_name),
+                generic: GenericParam::Type(generic),
+                sigs: vec![sig],
+            });
+        }
+    }
+    qualify_group_names(&mut groups)?;
+    Ok(groups)
+}
 
-            fn add <#common_generic> (&mut self) -> &mut Self {
-                #(#insert_blocks)*
-                self
+/// Renames groups sharing a name, like `a::Element` and `b::Element`, after their full paths
+/// like `a_element` and `b_element`. Groups that still share a name are reported.
+fn qualify_group_names(groups: &mut [BoundGroup]) -> Result<()> {
+    let names = groups
+        .iter()
+        .map(|group| group.name.clone())
+        .collect::<Vec<_>>();
+    for group in groups.iter_mut() {
+        if names.iter().filter(|name| **name == group.name).count() > 1 {
+            group.name = match &group.generic {
+                GenericParam::Const(param) => gen_const_group_name(&param.ty, qualified_name)?,
+                GenericParam::Type(param) => gen_group_name(param, qualified_name),
+                GenericParam::Lifetime(..) => continue,
+            };
+        }
+    }
+    for (i, group) in groups.iter().enumerate() {
+        if let Some(other) = groups[..i].iter().find(|other| other.name == group.name) {
+            let mut error = Error::new_spanned(
+                &group.sigs[0].generics,
+                format!(
+                    "the bounds of this method differ from those of `{}`, \
+                    but both would be registered by `with_{}`",
+                    other.sigs[0].ident, group.name
+                ),
+            );
+            error.combine(Error::new_spanned(
+                &other.sigs[0].generics,
+                format!("`{}` is declared here", other.sigs[0].ident),
+            ));
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+/// Names the const group after its type, e.g. `const` for `usize` and `const_u8` for `u8`.
+fn gen_const_group_name(ty: &Type, path_name: fn(&Path) -> String) -> Result<String> {
+    let name = match ty {
+        Type::Path(TypePath { qself: None, path }) => path_name(path),
+        _ => {
+            return Err(Error::new_spanned(
+                ty,
+                "expected the type of the const parameter like `usize`",
+            ))
+        }
+    };
+    Ok(if name == "usize" {
+        "const".to_owned()
+    } else {
+        format!("const_{name}")
+    })
+}
+
+/// Names the group after the traits in the bounds, e.g. `Component + Clone` becomes `component_clone`.
+/// If there's no trait, lifetimes or the generic parameter itself are used instead.
+/// `?Sized` parameters are separated from sized ones by `_unsized` suffix like `key_unsized`.
+fn gen_group_name(param: &TypeParam, path_name: fn(&Path) -> String) -> String {
+    let traits = param
+        .bounds
+        .iter()
+        .filter_map(|bound| match bound {
+            TypeParamBound::Trait(TraitBound {
+                path,
+                modifier: TraitBoundModifier::None,
+                ..
+            }) => Some(path_name(path)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let lifetimes = param
+        .bounds
+        .iter()
+        .filter_map(|bound| match bound {
+            TypeParamBound::Lifetime(lifetime) => Some(lifetime.ident.to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let name = if !traits.is_empty() {
+        traits.join("_")
+    } else if !lifetimes.is_empty() {
+        lifetimes.join("_")
+    } else {
+        snake_case(&param.ident.to_string())
+    };
+    if is_maybe_sized(param) {
+        format!("{name}_unsized")
+    } else {
+        name
+    }
+}
+
+/// Names the path after its last segment like `element` for `a::Element`.
+fn last_segment_name(path: &Path) -> String {
+    path.segments
+        .last()
+        .map(|seg| snake_case(&seg.ident.to_string()))
+        .unwrap_or_default()
+}
+
+/// Names the path after all of its segments like `a_element` for `a::Element`.
+fn qualified_name(path: &Path) -> String {
+    path.segments
+        .iter()
+        .map(|seg| snake_case(&seg.ident.to_string()))
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Writes the whole path including generic arguments, which distinguishes groups.
+fn path_tokens(path: &Path) -> String {
+    path.to_token_stream().to_string()
+}
+
+/// Generates a call of the method on the implementor like `(__self__).foo::<T>(a)`,
+/// or `<Handler as Generic>::foo::<T>(__self__, a)` with the generic trait.
+fn gen_call(
+    imp: &Implementor,
+    src_path: Option<&Path>,
+    sig: &Signature,
+    self_ident: TokenStream2,
+    turbofish: TokenStream2,
+    args: &[TokenStream2],
+) -> Result<TokenStream2> {
+    let method_ident = &sig.ident;
+    let receiver = imp.gen_receiver(sig, self_ident)?;
+    let target_ty = &imp.target_ty;
+    Ok(match src_path {
+        Some(src_path) => quote! {
+            <#target_ty as #src_path>::#method_ident #turbofish (#receiver, #(#args),*)
+        },
+        None => quote! { (#receiver).#method_ident #turbofish (#(#args),*) },
+    })
+}
+
+/// Generates code of inserting an entry into the table for the generic method.
+/// Each method has its own generic parameter, so the code is put in a function
+/// that has the same generic parameter with the method.
+/// Then the builder calls it with its own generic parameter.
+/// The entry is a function rather than a closure, so that it can return borrowed values.
+fn gen_insert_block(
+    imp: &Implementor,
+    src_path: Option<&Path>,
+    erased_path: &Path,
+    sig: &Signature,
+    builder_generic_ident: &Ident,
+) -> Result<TokenStream2> {
+    let builder_ident = &imp.builder;
+    let method_ident = &sig.ident;
+    let insert_ident = clone_ident_with_prefix(method_ident, "insert_");
+    let st_generics = &imp.generics;
+
+    // Callbacks are passed as closures, so they are not generic parameters of the entry.
+    let mut generics = sig.generics.clone();
+    let callback_symbols = generics
+        .params
+        .iter()
+        .filter(|param| is_callback_param(param))
+        .map(get_generic_symbol)
+        .collect::<Result<Vec<_>>>()?;
+    generics.params = generics
+        .params
+        .into_iter()
+        .filter(|param| !is_callback_param(param))
+        .collect();
+    if let Some(where_clause) = generics.where_clause.as_mut() {
+        where_clause.predicates = where_clause
+            .predicates
+            .iter()
+            .filter(|pred| !mentions_any(pred, callback_symbols.iter().map(|s| s.as_str())))
+            .cloned()
+            .collect();
+    }
+    let generic_params = &generics.params;
+
+    // Generic parameters of the struct are given to the nested functions as well.
+    let merged = merge_generics(st_generics, &generics);
+    let merged_params = &merged.params;
+    let where_clause = &merged.where_clause;
+    let st_lifetimes = st_generics.lifetimes();
+    let st_params = st_generics
+        .params
+        .iter()
+        .filter(|param| !matches!(param, GenericParam::Lifetime(..)))
+        .map(|param| {
+            let mut param = param.clone();
+            match &mut param {
+                GenericParam::Type(param) => param.default = None,
+                GenericParam::Const(param) => param.default = None,
+                GenericParam::Lifetime(..) => {}
             }
+            param
+        });
+    let st_args = get_generic_args(st_generics);
+    let (_, st_ty_generics, _) = st_generics.split_for_impl();
+
+    // Type generics are keyed by `TypeId`, and const generics are keyed by the value.
+    let erased_params = generic_params
+        .iter()
+        .filter(|param| !matches!(param, GenericParam::Lifetime(..)))
+        .collect::<Vec<_>>();
+    let erased_param_idents = erased_params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Const(ConstParam { ident, .. }) => ident,
+            GenericParam::Type(TypeParam { ident, .. }) => ident,
+            GenericParam::Lifetime(..) => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    let key = match erased_params.first() {
+        Some(GenericParam::Const(ConstParam { ident, .. })) => quote! { #ident },
+        Some(GenericParam::Type(TypeParam { ident, .. })) => {
+            quote! { std::any::TypeId::of::<#ident>() }
+        }
+        _ => {
+            return Err(Error::new_spanned(
+                &sig.generics,
+                "expected a generic type or const parameter",
+            ))
         }
     };
 
-    quote! {
-        #builder
-        #impl_builder
+    // Callback types are inferred from the closures.
+    // Type parameters of the trait are given to the trait, not to the method.
+    let trait_params = get_trait_params(src_path, &sig.generics);
+    let turbofish_args = sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(TypeParam { ident, .. }) if is_impl_trait_param(ident) => None,
+            GenericParam::Type(TypeParam { ident, .. }) if trait_params.contains(&ident) => None,
+            param if is_callback_param(param) => Some(quote! { _ }),
+            GenericParam::Type(TypeParam { ident, .. }) => Some(quote! { #ident }),
+            GenericParam::Const(ConstParam { ident, .. }) => Some(quote! { #ident }),
+            GenericParam::Lifetime(..) => None,
+        })
+        .collect::<Vec<_>>();
+    let turbofish = if turbofish_args.is_empty() {
+        quote! {}
+    } else {
+        quote! { ::<#(#turbofish_args),*> }
+    };
+
+    // Skips the receiver.
+    let (lifetimes, inputs, output) = get_entry_types(sig, &imp.ty, erased_path)?;
+    let self_ty = &inputs[0];
+    let arg_idents = get_idents(&sig.inputs)?;
+    let arg_idents = &arg_idents[1..];
+    let arg_types = &inputs[1..];
+
+    // Casts erased arguments back into the original types.
+    // Unsized arguments come in the pointer type of the erased trait.
+    let ptr_path = gen_ptr_path(erased_path);
+    let mut casted = Vec::new();
+    for arg in sig.inputs.iter().skip(1) {
+        let (ident, ty, _) = parse_arg(arg)?;
+        casted.push(match ErasedArg::new(ty, &sig.generics)? {
+            Some(erased) => erased.gen_downcast(ident, &sig.generics, &ptr_path)?,
+            None => quote! { #ident },
+        });
     }
+
+    // Casts the generic return value into the erased type.
+    // The trait is specified if it's given, so that methods of the same name in other traits
+    // implemented by the struct are not ambiguous.
+    let call = gen_call(imp, src_path, sig, quote! { __self__ }, turbofish, &casted)?;
+    let call = match get_erased_output(sig)? {
+        Some(erased) => {
+            let upcast = erased.gen_upcast(&format_ident!("__ret__"), &sig.generics, &ptr_path)?;
+            quote! {
+                let __ret__ = #call;
+                #upcast
+            }
+        }
+        None => call,
+    };
+    // Async? => Boxes the concrete future.
+    let call = match get_future_output(sig) {
+        Some(..) => quote! { std::boxed::Box::pin(#call) },
+        None => call,
+    };
+
+    Ok(quote! {
+        fn #insert_ident <#merged_params> (
+            table: &mut #builder_ident #st_ty_generics
+        ) #where_clause {
+            fn entry<#(#lifetimes,)* #(#st_lifetimes,)* #(#st_params,)* #(#erased_params),*>(
+                __self__: #self_ty,
+                #(#arg_idents: #arg_types),*
+            ) -> #output #where_clause {
+                #call
+            }
+
+            if let Some(map) = table.#method_ident.as_mut() {
+                map.insert(#key, entry::<#(#st_args,)* #(#erased_param_idents),*>);
+            }
+        }
+        #insert_ident::<#(#st_args,)* #builder_generic_ident>(self);
+    })
 }
@@ -3,7 +3,7 @@
 //! ## When to use
 //!
 //! - When you want to make a trait obejct from a trait having generic methods.
-//! - When generic methods have only one kind of bounds. (Please look at example below)
+//! - When each generic method has only one generic parameter. (Please look at example below)
 //! - When generic methods require 'static lifetime.
 //! - When you can know all types that are passed to generic methods.
 //!
@@ -127,13 +127,14 @@
 //!
 //! // Let's make an instance that implements generic.
 //! let mut handler = Handler {
-//!     // fn_table is injected by `inject_fn_table` macro.
+//!     // fn_table_erased_generic is injected by `inject_fn_table` macro.
+//!     // It's named after the erased trait, and the trait is given to the macros as well.
 //!     // You can omit A and B here.
-//!     fn_table: generate_fn_table!(Handler, A, B),
+//!     fn_table_erased_generic: generate_fn_table!(Handler as ErasedGeneric, A, B),
 //!     v: Vec::new(),
 //! };
 //! // We can add more entries before becoming a trait object.
-//! add_fn_table!(handler, C, D);
+//! add_fn_table!(handler as ErasedGeneric, C, D);
 //!
 //! // Constructs a trait object.
 //! // Currently, we can't add more entries using a trait object.
@@ -176,6 +177,774 @@
 //! assert_eq!("1234", trait_object.foo());
 //! ```
 //!
+//! ## Multiple kinds of bounds
+//!
+//! Generic methods are grouped by the bounds of their generic parameters.
+//! The function table gets `with_*` and `add_*` methods named after the bounds for each group,
+//! such as `add_component` for `C: Component`. Plain `with` and `add` are their aliases
+//! if there's only one group.
+//! Traits sharing a name like `a::Element` and `b::Element` are told apart by their paths,
+//! so their groups become `a_element` and `b_element`.
+//! A type is only required to satisfy the bounds of the group it's registered into.
+//!
+//! ```
+//! use erased_generic_trait::*;
+//!
+//! trait Component: 'static {}
+//! trait Message: 'static {}
+//!
+//! #[erase_generic(ErasedWorld)]
+//! trait World {
+//!     fn store<C: Component>(&mut self, c: &C) -> usize;
+//!     fn send<M: Message>(&mut self, m: &M) -> usize;
+//! }
+//!
+//! #[inject_fn_table(
+//!     World as ErasedWorld;
+//!     fn store<C: Component>(&mut self, c: &C) -> usize;
+//!     fn send<M: Message>(&mut self, m: &M) -> usize;
+//! )]
+//! struct Handler {}
+//!
+//! impl World for Handler {
+//!     fn store<C: Component>(&mut self, _c: &C) -> usize {
+//!         std::mem::size_of::<C>()
+//!     }
+//!
+//!     fn send<M: Message>(&mut self, _m: &M) -> usize {
+//!         std::mem::size_of::<M>()
+//!     }
+//! }
+//!
+//! struct Position(f32, f32);
+//! struct Ping(u8);
+//! impl Component for Position {}
+//! impl Message for Ping {}
+//!
+//! // Group names come after the types for `with` and `add`.
+//! let mut handler = Handler {
+//!     fn_table_erased_world: generate_fn_table!(Handler as ErasedWorld; component: Position),
+//! };
+//! add_fn_table!(handler as ErasedWorld; message: Ping);
+//!
+//! let mut trait_object: Box<dyn ErasedWorld> = Box::new(handler);
+//! assert_eq!(8, trait_object.store(&Position(0.0, 0.0)));
+//! assert_eq!(1, trait_object.send(&Ping(0)));
+//! ```
+//!
+//! ## Const generics
+//!
+//! Const generic methods are dispatched by the value of the const parameter instead of `TypeId`.
+//! Register values like types, e.g. `with_const::<16>()`, or `const: 16` in the macros.
+//! Const groups are named after the type of the parameter, such as `const` for `usize`
+//! and `const_u8` for `u8`.
+//!
+//! ```
+//! use erased_generic_trait::*;
+//!
+//! trait Element: 'static {}
+//!
+//! #[erase_generic(ErasedCodec)]
+//! trait Codec {
+//!     fn encode<E: Element>(&mut self, e: &E) -> usize;
+//!     fn chunk<const N: usize>(&mut self, data: &[u8; N]) -> usize;
+//! }
+//!
+//! #[inject_fn_table(
+//!     Codec as ErasedCodec;
+//!     fn encode<E: Element>(&mut self, e: &E) -> usize;
+//!     fn chunk<const N: usize>(&mut self, data: &[u8; N]) -> usize;
+//! )]
+//! struct Handler {}
+//!
+//! impl Codec for Handler {
+//!     fn encode<E: Element>(&mut self, _e: &E) -> usize {
+//!         std::mem::size_of::<E>()
+//!     }
+//!
+//!     fn chunk<const N: usize>(&mut self, _data: &[u8; N]) -> usize {
+//!         N
+//!     }
+//! }
+//!
+//! impl Element for u32 {}
+//!
+//! let handler = Handler {
+//!     fn_table_erased_codec: generate_fn_table!(Handler as ErasedCodec; element: u32; const: 2, 4),
+//! };
+//!
+//! let mut trait_object: Box<dyn ErasedCodec> = Box::new(handler);
+//! assert_eq!(4, trait_object.encode(&0_u32));
+//! assert_eq!(2, trait_object.chunk(&[0; 2]));
+//! assert_eq!(4, trait_object.chunk(&[0; 4]));
+//! ```
+//!
+//! Traits having only const generic methods are registered the same way.
+//!
+//! ```
+//! use erased_generic_trait::*;
+//!
+//! #[erase_generic(ErasedCodec)]
+//! trait Codec {
+//!     fn chunk<const N: usize>(&mut self, data: &[u8; N]) -> usize;
+//! }
+//!
+//! #[inject_fn_table(
+//!     Codec as ErasedCodec;
+//!     fn chunk<const N: usize>(&mut self, data: &[u8; N]) -> usize;
+//! )]
+//! struct Handler {}
+//!
+//! impl Codec for Handler {
+//!     fn chunk<const N: usize>(&mut self, _data: &[u8; N]) -> usize {
+//!         N
+//!     }
+//! }
+//!
+//! let mut handler = Handler {
+//!     fn_table_erased_codec: generate_fn_table!(Handler as ErasedCodec; const: 2),
+//! };
+//! handler.fn_table_erased_codec.add_const::<4>().add::<8>();
+//!
+//! let mut trait_object: Box<dyn ErasedCodec> = Box::new(handler);
+//! assert_eq!(2, trait_object.chunk(&[0; 2]));
+//! assert_eq!(4, trait_object.chunk(&[0; 4]));
+//! assert_eq!(8, trait_object.chunk(&[0; 8]));
+//! ```
+//!
+//! ## Unsized types
+//!
+//! Generic parameters bounded by `?Sized` can be erased as well when they're passed by reference.
+//! Then you can register unsized types such as `str` and `[u8]`.
+//! `?Sized` parameters are grouped separately with `_unsized` suffix like `with_key_unsized`.
+//!
+//! ```
+//! use erased_generic_trait::*;
+//!
+//! trait Key: 'static + AsRef<[u8]> {}
+//!
+//! #[erase_generic(ErasedTable)]
+//! trait Table {
+//!     fn get<Q: ?Sized + Key>(&self, q: &Q) -> usize;
+//! }
+//!
+//! #[inject_fn_table(
+//!     Table as ErasedTable;
+//!     fn get<Q: ?Sized + Key>(&self, q: &Q) -> usize;
+//! )]
+//! struct Handler {}
+//!
+//! impl Table for Handler {
+//!     fn get<Q: ?Sized + Key>(&self, q: &Q) -> usize {
+//!         q.as_ref().len()
+//!     }
+//! }
+//!
+//! impl Key for str {}
+//! impl Key for [u8] {}
+//!
+//! let handler = Handler {
+//!     fn_table_erased_table: generate_fn_table!(Handler as ErasedTable, str, [u8]),
+//! };
+//!
+//! let trait_object: Box<dyn ErasedTable> = Box::new(handler);
+//! assert_eq!(5, trait_object.get("hello"));
+//! assert_eq!(2, trait_object.get(&[0_u8, 1][..]));
+//! ```
+//!
+//! Unsized arguments are passed to the erased methods as pointers wrapped in a hidden type of
+//! the erased trait, which can't be made without `unsafe`. So pointers from callers are rejected
+//! rather than dereferenced.
+//!
+//! ```should_panic
+//! # use erased_generic_trait::*;
+//! # trait Key: 'static + AsRef<[u8]> {}
+//! # #[erase_generic(ErasedTable)]
+//! # trait Table {
+//! #     fn get<Q: ?Sized + Key>(&self, q: &Q) -> usize;
+//! # }
+//! # #[inject_fn_table(
+//! #     Table as ErasedTable;
+//! #     fn get<Q: ?Sized + Key>(&self, q: &Q) -> usize;
+//! # )]
+//! # struct Handler {}
+//! # impl Table for Handler {
+//! #     fn get<Q: ?Sized + Key>(&self, q: &Q) -> usize {
+//! #         q.as_ref().len()
+//! #     }
+//! # }
+//! # impl Key for str {}
+//! use std::any::{Any, TypeId};
+//!
+//! let handler = Handler {
+//!     fn_table_erased_table: generate_fn_table!(Handler as ErasedTable, str),
+//! };
+//! let trait_object: Box<dyn ErasedTable> = Box::new(handler);
+//!
+//! // Panics instead of reading the dangling pointer.
+//! let ptr = std::ptr::slice_from_raw_parts(std::ptr::null::<u8>(), 5) as *const str;
+//! trait_object.erased_get(&TypeId::of::<str>(), &ptr as &dyn Any);
+//! ```
+//!
+//! The hidden type can't be made directly even in the module of the erased trait.
+//!
+//! ```compile_fail,E0423
+//! # use erased_generic_trait::*;
+//! # trait Key: 'static + AsRef<[u8]> {}
+//! #[erase_generic(ErasedTable)]
+//! trait Table {
+//!     fn get<Q: ?Sized + Key>(&self, q: &Q) -> usize;
+//! }
+//!
+//! let ptr = std::ptr::slice_from_raw_parts(std::ptr::null::<u8>(), 5) as *const str;
+//! let _ = ErasedTablePtr(ptr);
+//! ```
+//!
+//! ## Callbacks
+//!
+//! Callbacks receiving generic types, like `&mut dyn FnMut(&E)` or `F: FnMut(&E)`,
+//! are erased into `&mut dyn FnMut(&dyn Any)`, or `&dyn Fn(&dyn Any)` for `Fn`.
+//! Trait objects wrap your callbacks so that they receive the concrete types.
+//!
+//! ```
+//! use erased_generic_trait::*;
+//! use std::any::Any;
+//!
+//! trait Element: 'static {}
+//!
+//! #[erase_generic(ErasedStore)]
+//! trait Store {
+//!     fn for_each<E: Element>(&self, f: &mut dyn FnMut(&E));
+//!     fn retain<E: Element, F: FnMut(&E) -> bool>(&mut self, f: F);
+//! }
+//!
+//! #[inject_fn_table(
+//!     Store as ErasedStore;
+//!     fn for_each<E: Element>(&self, f: &mut dyn FnMut(&E));
+//!     fn retain<E: Element, F: FnMut(&E) -> bool>(&mut self, f: F);
+//! )]
+//! struct Handler {
+//!     v: Vec<Box<dyn Any>>,
+//! }
+//!
+//! impl Store for Handler {
+//!     fn for_each<E: Element>(&self, f: &mut dyn FnMut(&E)) {
+//!         self.v.iter().filter_map(|v| v.downcast_ref::<E>()).for_each(f);
+//!     }
+//!
+//!     fn retain<E: Element, F: FnMut(&E) -> bool>(&mut self, mut f: F) {
+//!         self.v.retain(|v| v.downcast_ref::<E>().map_or(true, &mut f));
+//!     }
+//! }
+//!
+//! impl Element for i32 {}
+//!
+//! let handler = Handler {
+//!     fn_table_erased_store: generate_fn_table!(Handler as ErasedStore, i32),
+//!     v: vec![Box::new(1), Box::new(2), Box::new(3)],
+//! };
+//!
+//! let mut trait_object: Box<dyn ErasedStore> = Box::new(handler);
+//! trait_object.retain(|v: &i32| v % 2 == 1);
+//! let mut sum = 0;
+//! trait_object.for_each(&mut |v: &i32| sum += v);
+//! assert_eq!(4, sum);
+//! ```
+//!
+//! ## Receivers and return values
+//!
+//! Besides `&self` and `&mut self`, methods may take `Box<Self>`, `Rc<Self>`, `Arc<Self>` and
+//! `Pin<&mut Self>` receivers. Generic types can also be passed or returned by value, which are
+//! boxed into `Box<dyn Any>` in the erased trait.
+//!
+//! ```
+//! use erased_generic_trait::*;
+//! use std::any::Any;
+//!
+//! trait Element: 'static {}
+//!
+//! #[erase_generic(ErasedStack)]
+//! trait Stack {
+//!     fn push<E: Element>(&mut self, e: E);
+//!     fn peek<E: Element>(&self) -> Option<&E>;
+//!     fn finish<E: Element>(self: Box<Self>) -> E;
+//! }
+//!
+//! #[inject_fn_table(
+//!     Stack as ErasedStack;
+//!     fn push<E: Element>(&mut self, e: E);
+//!     fn peek<E: Element>(&self) -> Option<&E>;
+//!     fn finish<E: Element>(self: Box<Self>) -> E;
+//! )]
+//! struct Handler {
+//!     v: Vec<Box<dyn Any>>,
+//! }
+//!
+//! impl Stack for Handler {
+//!     fn push<E: Element>(&mut self, e: E) {
+//!         self.v.push(Box::new(e));
+//!     }
+//!
+//!     fn peek<E: Element>(&self) -> Option<&E> {
+//!         self.v.last().and_then(|v| v.downcast_ref::<E>())
+//!     }
+//!
+//!     fn finish<E: Element>(mut self: Box<Self>) -> E {
+//!         *self.v.pop().unwrap().downcast::<E>().unwrap()
+//!     }
+//! }
+//!
+//! impl Element for i32 {}
+//!
+//! let handler = Handler {
+//!     fn_table_erased_stack: generate_fn_table!(Handler as ErasedStack, i32),
+//!     v: Vec::new(),
+//! };
+//!
+//! let mut trait_object: Box<dyn ErasedStack> = Box::new(handler);
+//! trait_object.push(42_i32);
+//! assert_eq!(Some(&42), trait_object.peek::<i32>());
+//! assert_eq!(42, trait_object.finish::<i32>());
+//! ```
+//!
+//! ## Associated types and constants
+//!
+//! Associated types are kept in the erased trait, so trait objects name them like
+//! `dyn ErasedGeneric<Output = u32>`. Associated constants are replaced with accessor methods
+//! like `erased_const_kind()` for `KIND`, which give the values of implementors. Trait objects
+//! can't give the values through the generic trait, so `<dyn ErasedParser as Parser>::KIND` is
+//! the default value if the constant has one, or a compile error otherwise. List them in
+//! `inject_fn_table` like `type Output;` or `const KIND: u8;`, then their values come from the
+//! generic trait implementation. Associated types can also be given like `type Output = u32;`.
+//!
+//! ```
+//! use erased_generic_trait::*;
+//!
+//! trait Element: 'static {}
+//!
+//! #[erase_generic(ErasedParser)]
+//! trait Parser {
+//!     type Output;
+//!     const KIND: u8;
+//!     const VERSION: u8 = 0;
+//!     fn parse<E: Element>(&self, e: &E) -> Self::Output;
+//! }
+//!
+//! #[inject_fn_table(
+//!     Parser as ErasedParser;
+//!     type Output;
+//!     const KIND: u8;
+//!     const VERSION: u8;
+//!     fn parse<E: Element>(&self, e: &E) -> Self::Output;
+//! )]
+//! struct Handler {}
+//!
+//! impl Parser for Handler {
+//!     type Output = usize;
+//!     const KIND: u8 = 1;
+//!     const VERSION: u8 = 2;
+//!
+//!     fn parse<E: Element>(&self, _e: &E) -> usize {
+//!         std::mem::size_of::<E>()
+//!     }
+//! }
+//!
+//! impl Element for u64 {}
+//!
+//! let handler = Handler {
+//!     fn_table_erased_parser: generate_fn_table!(Handler as ErasedParser, u64),
+//! };
+//!
+//! let trait_object: Box<dyn ErasedParser<Output = usize>> = Box::new(handler);
+//! assert_eq!(8, trait_object.parse(&0_u64));
+//! assert_eq!(1, trait_object.erased_const_kind());
+//! assert_eq!(2, trait_object.erased_const_version());
+//!
+//! // Trait objects report the default value through the generic trait.
+//! assert_eq!(0, <dyn ErasedParser<Output = usize> as Parser>::VERSION);
+//! ```
+//!
+//! Constants without default values can't be read through trait objects.
+//!
+//! ```compile_fail,E0080
+//! # use erased_generic_trait::*;
+//! #[erase_generic(ErasedParser)]
+//! trait Parser {
+//!     const KIND: u8;
+//!     fn parse<E: 'static>(&self, e: &E) -> usize;
+//! }
+//!
+//! // error: trait objects can't give `KIND`; call `erased_const_kind()` instead
+//! let kind = <dyn ErasedParser as Parser>::KIND;
+//! ```
+//!
+//! ## Generic traits
+//!
+//! Generic parameters of the trait are carried onto the erased trait, like
+//! `ErasedStorage<K>` for `Storage<K>`. Give concrete arguments to `inject_fn_table`, and write
+//! the signatures with them.
+//!
+//! ```
+//! # use erased_generic_trait::*;
+//! # trait Element: 'static {}
+//! #[erase_generic(ErasedStorage)]
+//! trait Storage<K> {
+//!     fn put<E: Element>(&mut self, k: K, e: &E);
+//! }
+//!
+//! #[inject_fn_table(
+//!     Storage<u32> as ErasedStorage<u32>;
+//!     fn put<E: Element>(&mut self, k: u32, e: &E);
+//! )]
+//! struct Handler {}
+//! # impl Storage<u32> for Handler {
+//! #     fn put<E: Element>(&mut self, _k: u32, _e: &E) {}
+//! # }
+//! ```
+//!
+//! ## Erasing type parameters of traits
+//!
+//! `erase(T)` erases a type parameter of the trait as well, so one `dyn ErasedSink` serves every
+//! `Sink<T>`. Methods not taking `T` must be skipped by `#[erased(skip)]`, and `T` is written as
+//! a generic parameter of the methods in `inject_fn_table`.
+//!
+//! ```
+//! # use erased_generic_trait::*;
+//! # trait Element: 'static {}
+//! #[erase_generic(ErasedSink, erase(T))]
+//! trait Sink<T: Element> {
+//!     fn push(&mut self, t: &T);
+//!     #[erased(skip)]
+//!     fn len(&self) -> usize;
+//! }
+//!
+//! #[inject_fn_table(
+//!     Sink<T> as ErasedSink;
+//!     fn push<T: Element>(&mut self, t: &T);
+//! )]
+//! struct Handler {}
+//! # impl<T: Element> Sink<T> for Handler {
+//! #     fn push(&mut self, _t: &T) {}
+//! #     fn len(&self) -> usize { 0 }
+//! # }
+//! ```
+//!
+//! ## Supertraits
+//!
+//! Give erased supertraits like `supertrait(Base = ErasedBase)`, listing all of them up the chain.
+//! Then `ErasedDerived: ErasedBase`, and trait objects can be upcast to `dyn ErasedBase`. Put
+//! `inject_fn_table` for each erased trait on the struct, and each table is named after its
+//! erased trait like `fn_table_erased_base`.
+//!
+//! ```
+//! # use erased_generic_trait::*;
+//! # trait Element: 'static {}
+//! #[erase_generic(ErasedBase)]
+//! trait Base {
+//!     fn size<E: Element>(&self, e: &E) -> usize;
+//! }
+//!
+//! #[erase_generic(ErasedDerived, supertrait(Base = ErasedBase))]
+//! trait Derived: Base {
+//!     fn describe<E: Element>(&self, e: &E) -> String;
+//! }
+//! ```
+//!
+//! ## Generic implementors
+//!
+//! Structs can have generic parameters and lifetimes like `Handler<'a, T>`, and implement the
+//! generic trait with the same bounds as the struct. Trait objects borrowing something are written
+//! like `dyn ErasedGeneric + 'a`.
+//!
+//! ## Tuple structs, unit structs and enums
+//!
+//! Tuple structs get the function table as the last field, and unit structs become tuple structs
+//! having only the table. Enums are wrapped in a struct like `StateWithFnTable { inner,
+//! fn_table_erased_generic }`, which derefs to the enum and supports only `&self` and `&mut self`
+//! receivers. `add_fn_table!` finds tables by their field names, so add types to tuple structs
+//! like `handler.1.add::<A>()` instead.
+//!
+//! ```
+//! # use erased_generic_trait::*;
+//! # trait Element: 'static {}
+//! # #[erase_generic(ErasedGeneric)]
+//! # trait Generic {
+//! #     fn size<E: Element>(&self, e: &E) -> usize;
+//! # }
+//! #[inject_fn_table(
+//!     Generic as ErasedGeneric;
+//!     fn size<E: Element>(&self, e: &E) -> usize;
+//! )]
+//! enum State {
+//!     Idle,
+//! }
+//! # impl Generic for State {
+//! #     fn size<E: Element>(&self, _e: &E) -> usize { 0 }
+//! # }
+//! # struct A;
+//! # impl Element for A {}
+//!
+//! let state = StateWithFnTable {
+//!     inner: State::Idle,
+//!     fn_table_erased_generic: generate_fn_table!(State as ErasedGeneric, A),
+//! };
+//! ```
+//!
+//! ## Table field options
+//!
+//! `field(..)` configures the injected field like a field, e.g.
+//! `field(#[serde(skip)] pub(crate) handlers)`, and each part can be omitted. The function table
+//! implements `Default`, `Clone` and `Debug`, and `Default` fills it with the types given by
+//! `default(..)`. Add types to renamed fields like `handler.handlers.add::<A>()`.
+//!
+//! ```
+//! # use erased_generic_trait::*;
+//! # trait Element: 'static {}
+//! # #[erase_generic(ErasedGeneric)]
+//! # trait Generic {
+//! #     fn size<E: Element>(&self, e: &E) -> usize;
+//! # }
+//! # struct A;
+//! # impl Element for A {}
+//! # struct B;
+//! # impl Element for B {}
+//! #[inject_fn_table(
+//!     Generic as ErasedGeneric, field(pub handlers), default(A, B);
+//!     fn size<E: Element>(&self, e: &E) -> usize;
+//! )]
+//! #[derive(Default, Clone, Debug)]
+//! pub struct Handler {}
+//! # impl Generic for Handler {
+//! #     fn size<E: Element>(&self, _e: &E) -> usize { 0 }
+//! # }
+//! ```
+//!
+//! ## Visibility
+//!
+//! The function table builder, its methods and the injected field have the visibility of the
+//! struct, so structs in other modules get their tables like
+//! `generate_fn_table!(handlers::Handler<u32> as ErasedGeneric, A)`.
+//!
+//! The builder is generated next to the struct rather than in a nested module, since a module
+//! can't see items declared in a function body. It's the only named item, and it's named after
+//! the struct and the erased trait like `HandlerErasedGenericFnTable`, so several implementors of
+//! a trait can live in one module.
+//!
+//! ## Default methods
+//!
+//! Default bodies stay in the generic trait, and the erased trait only declares the methods.
+//! So the default bodies are called through trait objects unless the struct overrides them.
+//!
+//! ```
+//! use erased_generic_trait::*;
+//!
+//! trait Element: 'static {}
+//!
+//! #[erase_generic(ErasedMeasure)]
+//! trait Measure {
+//!     fn base(&self) -> usize;
+//!     fn size<E: Element>(&self, _e: &E) -> usize {
+//!         std::mem::size_of::<E>() + self.base()
+//!     }
+//! }
+//!
+//! #[inject_fn_table(
+//!     Measure as ErasedMeasure;
+//!     fn base(&self) -> usize;
+//!     fn size<E: Element>(&self, _e: &E) -> usize;
+//! )]
+//! struct Handler {}
+//!
+//! impl Measure for Handler {
+//!     fn base(&self) -> usize {
+//!         10
+//!     }
+//! }
+//!
+//! impl Element for u32 {}
+//!
+//! let handler = Handler {
+//!     fn_table_erased_measure: generate_fn_table!(Handler as ErasedMeasure, u32),
+//! };
+//!
+//! let trait_object: Box<dyn ErasedMeasure> = Box::new(handler);
+//! assert_eq!(14, trait_object.size(&0_u32));
+//! ```
+//!
+//! ## Methods not callable on trait objects
+//!
+//! Associated functions without a receiver and methods taking or returning `Self` can't be
+//! called on trait objects. They are bounded by `where Self: Sized` in the generic trait and left
+//! out of the erased trait, so that trait objects can still be made. Such methods don't need to
+//! be listed in `inject_fn_table`, but they are checked against the trait if listed.
+//! Note that the bound changes the generic trait, so the methods can't be called on
+//! `T: ?Sized + Shape` either. The docs of the methods and the erased trait say so.
+//!
+//! ```
+//! use erased_generic_trait::*;
+//!
+//! trait Element: 'static {}
+//!
+//! #[erase_generic(ErasedShape)]
+//! trait Shape {
+//!     fn new() -> Self;
+//!     fn merge(&mut self, other: Self);
+//!     fn size<E: Element>(&self, e: &E) -> usize;
+//! }
+//!
+//! #[inject_fn_table(
+//!     Shape as ErasedShape;
+//!     fn new() -> Self;
+//!     fn size<E: Element>(&self, e: &E) -> usize;
+//! )]
+//! struct Handler {
+//!     n: usize,
+//! }
+//!
+//! impl Shape for Handler {
+//!     fn new() -> Self {
+//!         Handler {
+//!             fn_table_erased_shape: generate_fn_table!(Handler as ErasedShape, u8),
+//!             n: 1,
+//!         }
+//!     }
+//!
+//!     fn merge(&mut self, other: Self) {
+//!         self.n += other.n;
+//!     }
+//!
+//!     fn size<E: Element>(&self, _e: &E) -> usize {
+//!         std::mem::size_of::<E>() * self.n
+//!     }
+//! }
+//!
+//! impl Element for u8 {}
+//!
+//! let mut handler = Handler::new();
+//! handler.merge(Handler::new());
+//!
+//! let trait_object: Box<dyn ErasedShape> = Box::new(handler);
+//! assert_eq!(2, trait_object.size(&0_u8));
+//! ```
+//!
+//! ## Skipping and renaming methods
+//!
+//! Put `#[erased(skip)]` on a method to leave it out of the erased trait like methods not
+//! callable on trait objects. Erased methods are named like `erased_foo` by default, and
+//! `#[erased(rename = "dyn_foo")]` gives another name. Put the same attributes on the
+//! signatures in `inject_fn_table` as well.
+//!
+//! ```
+//! use erased_generic_trait::*;
+//!
+//! trait Element: 'static {}
+//!
+//! #[erase_generic(ErasedCounter)]
+//! trait Counter {
+//!     #[erased(rename = "dyn_count")]
+//!     fn count<E: Element>(&self, e: &E) -> usize;
+//!     #[erased(skip)]
+//!     fn count_static<E: Element>(&self) -> usize;
+//! }
+//!
+//! #[inject_fn_table(
+//!     Counter as ErasedCounter;
+//!     #[erased(rename = "dyn_count")]
+//!     fn count<E: Element>(&self, e: &E) -> usize;
+//!     #[erased(skip)]
+//!     fn count_static<E: Element>(&self) -> usize;
+//! )]
+//! struct Handler {}
+//!
+//! impl Counter for Handler {
+//!     fn count<E: Element>(&self, _e: &E) -> usize {
+//!         std::mem::size_of::<E>()
+//!     }
+//!
+//!     fn count_static<E: Element>(&self) -> usize {
+//!         std::mem::size_of::<E>()
+//!     }
+//! }
+//!
+//! impl Element for u16 {}
+//!
+//! let handler = Handler {
+//!     fn_table_erased_counter: generate_fn_table!(Handler as ErasedCounter, u16),
+//! };
+//! assert_eq!(2, handler.count_static::<u16>());
+//!
+//! let trait_object: Box<dyn ErasedCounter> = Box::new(handler);
+//! assert_eq!(2, trait_object.count(&0_u16));
+//! ```
+//!
+//! ## Async methods
+//!
+//! Async methods become methods returning `Pin<Box<dyn Future<Output = T> + '_>>` in the erased
+//! trait, and the function table boxes the concrete futures. Declare a method as
+//! `fn foo(..) -> impl Future<Output = T> + Send` to get `Send` futures instead, which requires
+//! the trait to be `Send`, or `Send + Sync` for `&self` methods, as well.
+//!
+//! ```
+//! use erased_generic_trait::*;
+//! use std::future::Future;
+//! use std::pin::pin;
+//! use std::task::{Context, Poll, Waker};
+//!
+//! trait Element: 'static + Sync {}
+//!
+//! #[erase_generic(ErasedStorage)]
+//! trait Storage: Send + Sync {
+//!     async fn save<E: Element>(&mut self, e: &E) -> usize;
+//!     fn load<E: Element>(&self) -> impl Future<Output = usize> + Send;
+//! }
+//!
+//! #[inject_fn_table(
+//!     Storage as ErasedStorage;
+//!     async fn save<E: Element>(&mut self, e: &E) -> usize;
+//!     fn load<E: Element>(&self) -> impl Future<Output = usize> + Send;
+//! )]
+//! struct Handler {
+//!     saved: usize,
+//! }
+//!
+//! impl Storage for Handler {
+//!     async fn save<E: Element>(&mut self, _e: &E) -> usize {
+//!         self.saved += std::mem::size_of::<E>();
+//!         self.saved
+//!     }
+//!
+//!     fn load<E: Element>(&self) -> impl Future<Output = usize> + Send {
+//!         async move { self.saved / std::mem::size_of::<E>() }
+//!     }
+//! }
+//!
+//! impl Element for u32 {}
+//!
+//! // Trivial executor for futures that are ready without waiting.
+//! fn block_on<F: Future>(fut: F) -> F::Output {
+//!     let mut fut = pin!(fut);
+//!     let mut cx = Context::from_waker(Waker::noop());
+//!     loop {
+//!         if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+//!             return output;
+//!         }
+//!     }
+//! }
+//!
+//! let handler = Handler {
+//!     fn_table_erased_storage: generate_fn_table!(Handler as ErasedStorage, u32),
+//!     saved: 0,
+//! };
+//!
+//! let mut trait_object: Box<dyn ErasedStorage> = Box::new(handler);
+//! assert_eq!(4, block_on(trait_object.save(&1_u32)));
+//! assert_eq!(8, block_on(trait_object.save(&2_u32)));
+//!
+//! // `Send` futures can be spawned on other threads.
+//! let load = trait_object.load::<u32>();
+//! assert_eq!(2, std::thread::scope(|s| s.spawn(|| block_on(load)).join().unwrap()));
+//! ```
+//!
 //! ## Pattern explanation
 //!
 //! <https://github.com/ecoricemon/erased-generic-trait/blob/main/examples/pattern/main.rs>
@@ -191,7 +960,12 @@ mod inject_fn_table;
 /// Generates a new trait that doesn't have generic methods in it.
 /// All generic methods are changed into non-generic methods,
 /// which have names like `erased_foo()`.
-/// Currently, supports only one generic parameter for all methods.
+/// Methods that can't be called on trait objects get `where Self: Sized` and are left out.
+/// The bound is added to the generic trait itself, and noted in the docs of the methods.
+/// `#[erased(skip)]` and `#[erased(rename = "dyn_foo")]` on methods control this per method.
+/// A type parameter of the trait can be erased as well like `#[erase_generic(ErasedSink, erase(T))]`.
+/// Erased supertraits are given like `supertrait(Base = ErasedBase)`.
+/// Currently, supports one erased type/const parameter per method, besides callback parameters.
 ///
 /// # Examples
 ///
@@ -202,11 +976,44 @@ mod inject_fn_table;
 /// // Put in a new trait name.
 /// #[erase_generic(ErasedGeneric)]
 /// trait Generic {
-///     // Must receive &mut self for now.
-///     // Must receive generic arguments as & or &mut for now.
+///     // Must receive &self, &mut self, Box<Self>, Rc<Self>, Arc<Self> or Pin<&mut Self> for now.
+///     // Must receive generic arguments as T, &, &mut, &[T], Option<&T>, Box<T> or callbacks for now.
 ///     fn generic<E: Element>(&mut self, param: &mut E);
 /// }
 /// ```
+///
+/// Unsupported signatures are reported at the offending part of the method.
+/// The messages are checked by the UI tests in `tests/ui`.
+///
+/// ```compile_fail
+/// # use erased_generic_trait::*;
+/// #[erase_generic(ErasedGeneric)]
+/// trait Generic {
+///     // error: only plain identifiers are supported as argument patterns; ..
+///     fn generic<E: 'static>(&self, (a, _b): (u8, &E)) -> u8 {
+///         a
+///     }
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use erased_generic_trait::*;
+/// #[erase_generic(ErasedGeneric)]
+/// trait Generic {
+///     // error: only `&self`, `&mut self`, `Box<Self>`, `Rc<Self>`, `Arc<Self>` and
+///     // `Pin<&mut Self>` receivers are supported for now; ..
+///     fn generic<E: 'static>(self: &std::rc::Rc<Self>, e: &E);
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use erased_generic_trait::*;
+/// #[erase_generic(ErasedGeneric)]
+/// trait Generic {
+///     // error: only one generic parameter per method is supported for now; ..
+///     fn generic<E: 'static, F: 'static>(&self, e: &E, f: &F);
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn erase_generic(attr: TokenStream, item: TokenStream) -> TokenStream {
     erase_generic::erase_generic(attr, item)
@@ -215,6 +1022,19 @@ pub fn erase_generic(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// Injects a function table into the struct in order to dispatch generic methods dynamically.
 /// Please put in the new trait name you used at the generic trait,
 /// and method signatures of the generic trait.
+/// You can also put in the generic trait like `Generic as ErasedGeneric`,
+/// then errors name the trait method when a signature doesn't match the trait.
+/// For non-generic structs, the generated implementation of the erased trait depends on the check,
+/// so rustc reports the check instead of E0053, and errors in the generated bodies may follow it.
+/// A different number of arguments is still reported by rustc first as E0050.
+/// Generic structs can't be checked that way, so rustc may report E0053 before the check.
+/// Associated types and constants like `type Output;` need the generic trait to get their values.
+/// Structs can have generic parameters and lifetimes, and the generated items take them as well.
+/// The builder of the table, its methods and the field have the same visibility with the struct.
+/// Tuple structs get the table as the last field, unit structs become tuple structs having only
+/// the table, and enums are wrapped in a struct holding the table like `StateWithFnTable`.
+/// The injected field is configured like `ErasedGeneric, field(#[serde(skip)] pub handlers)`,
+/// and `default(A, B)` gives the types that `Default` of the table is filled with.
 ///
 /// # Examples
 ///
@@ -235,6 +1055,30 @@ pub fn erase_generic(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// struct Handler {}
 ///
 /// ```
+///
+/// Signatures that don't match the generic trait are reported at the method name.
+///
+/// ```compile_fail,E0277
+/// # use erased_generic_trait::*;
+/// # trait Element: 'static {}
+/// #[erase_generic(ErasedGeneric)]
+/// trait Generic {
+///     fn size<E: Element>(&self, e: &E) -> usize;
+/// }
+///
+/// #[inject_fn_table(
+///     Generic as ErasedGeneric;
+///     // error: signature of `size` does not match `Generic::size`
+///     fn size<E: Element>(&mut self, e: &E) -> usize;
+/// )]
+/// struct Handler {}
+///
+/// impl Generic for Handler {
+///     fn size<E: Element>(&self, _e: &E) -> usize {
+///         std::mem::size_of::<E>()
+///     }
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn inject_fn_table(attr: TokenStream, item: TokenStream) -> TokenStream {
     inject_fn_table::inject_fn_table(attr, item)
@@ -242,6 +1086,12 @@ pub fn inject_fn_table(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 /// Generates a new function table for you.
 /// Please use this macro at the constuctors of your generic implementations.
+/// Give the erased trait of the table like `generate_fn_table!(Handler as ErasedGeneric, A, B)`.
+/// If generic methods have different bounds, put types for each group after its name
+/// like `generate_fn_table!(Handler as ErasedGeneric; component: A, B; message: C)`.
+/// Const generic methods take values like `generate_fn_table!(Handler as ErasedGeneric; const: 16)`.
+/// Structs in other modules or crates are given by their paths
+/// like `generate_fn_table!(handlers::Handler<u32> as ErasedGeneric, A)`.
 ///
 /// # Examples
 ///
@@ -269,7 +1119,7 @@ pub fn inject_fn_table(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// // Assumes that `Handler` here is a implementation of your generic trait.
 /// let handler = Handler {
 ///     // fn_table is injected by `inject_fn_table` macro.
-///     fn_table: generate_fn_table!(Handler, A,B),
+///     fn_table_erased_generic: generate_fn_table!(Handler as ErasedGeneric, A,B),
 ///     // Else you need to initialize.
 /// };
 /// ```
@@ -280,6 +1130,10 @@ pub fn generate_fn_table(input: TokenStream) -> TokenStream {
 
 /// Adds new entries into a function table for you.
 /// You can use this before becoming a trait object.
+/// Give the erased trait of the table like `add_fn_table!(handler as ErasedGeneric, A, B)`.
+/// If generic methods have different bounds, put types for each group after its name
+/// like `add_fn_table!(handler as ErasedGeneric; component: A, B; message: C)`.
+/// Const generic methods take values like `add_fn_table!(handler as ErasedGeneric; const: 16)`.
 ///
 /// # Examples
 ///
@@ -307,10 +1161,10 @@ pub fn generate_fn_table(input: TokenStream) -> TokenStream {
 /// // Assumes that `Handler` here is a implementation of your generic trait.
 /// let mut handler = Handler {
 ///     // fn_table is injected by `inject_fn_table` macro.
-///     fn_table: generate_fn_table!(Handler),
+///     fn_table_erased_generic: generate_fn_table!(Handler as ErasedGeneric),
 ///     // Else you need to initialize.
 /// };
-/// add_fn_table!(handler, A, B);
+/// add_fn_table!(handler as ErasedGeneric, A, B);
 /// ```
 #[proc_macro]
 pub fn add_fn_table(input: TokenStream) -> TokenStream {
@@ -0,0 +1,7 @@
+// Checks the errors reported for unsupported or mismatching input.
+// Run with `TRYBUILD=overwrite` to update the expected outputs after changing messages.
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
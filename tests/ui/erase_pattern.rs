@@ -0,0 +1,11 @@
+use erased_generic_trait::*;
+
+// Patterns are fine in methods having bodies, but the erased method can't take them.
+#[erase_generic(ErasedGeneric)]
+trait Generic {
+    fn generic<E: 'static>(&self, (a, _b): (u8, &E)) -> u8 {
+        a
+    }
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use erased_generic_trait::*;
+
+#[erase_generic(ErasedGeneric)]
+trait Generic {
+    fn generic<E: 'static>(self: &std::rc::Rc<Self>, e: &E);
+}
+
+fn main() {}
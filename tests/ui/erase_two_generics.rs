@@ -0,0 +1,8 @@
+use erased_generic_trait::*;
+
+#[erase_generic(ErasedGeneric)]
+trait Generic {
+    fn generic<E: 'static, F: 'static>(&self, e: &E, f: &F);
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+use erased_generic_trait::*;
+
+trait Element: 'static {}
+
+#[erase_generic(ErasedGeneric)]
+trait Generic {
+    fn size<E: Element>(&self, e: &E) -> usize;
+    fn count(&self, n: u32) -> u32;
+}
+
+// Only the checks are reported, not mismatches of the generated implementation.
+#[inject_fn_table(
+    Generic as ErasedGeneric;
+    fn size<E: Element>(&mut self, e: &E) -> usize;
+    fn count(&mut self, n: u32) -> u32;
+)]
+struct Handler {}
+
+impl Generic for Handler {
+    fn size<E: Element>(&self, _e: &E) -> usize {
+        std::mem::size_of::<E>()
+    }
+
+    fn count(&self, n: u32) -> u32 {
+        n
+    }
+}
+
+fn main() {}